@@ -0,0 +1,128 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    Key, XChaCha20Poly1305, XNonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, rand_core::RngCore},
+};
+use serde::{Deserialize, Serialize};
+
+use super::Secret;
+use crate::types::Error;
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+/// Argon2id parameters, stored next to the sealed data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory size in KiB
+    pub memory: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Data encrypted with XChaCha20-Poly1305 using a passphrase-derived key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sealed {
+    pub kdf: KdfParams,
+    /// Base64 encoded
+    pub salt: String,
+    /// Base64 encoded
+    pub nonce: String,
+    /// Base64 encoded
+    pub ciphertext: String,
+}
+
+/// Key derived from the passphrase
+///
+/// Kept in memory after unlock, so the store can be saved without asking
+/// for the passphrase again
+#[derive(Clone)]
+pub struct SealingKey {
+    kdf: KdfParams,
+    salt: Vec<u8>,
+    key: [u8; KEY_LEN],
+}
+
+impl std::fmt::Debug for SealingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, Error> {
+    STANDARD
+        .decode(value)
+        .map_err(|err| Error::Auth(format!("Invalid encrypted data: {err}")))
+}
+
+fn derive_key(passphrase: &Secret, salt: &[u8], kdf: KdfParams) -> Result<[u8; KEY_LEN], Error> {
+    let params = Params::new(kdf.memory, kdf.iterations, kdf.parallelism, Some(KEY_LEN))
+        .map_err(|err| Error::Auth(format!("Invalid key derivation params: {err}")))?;
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.expose().as_bytes(), salt, &mut key)
+        .map_err(|err| Error::Auth(format!("Failed to derive key: {err}")))?;
+    Ok(key)
+}
+
+impl SealingKey {
+    /// Derive new key with a random salt
+    pub fn new(passphrase: &Secret) -> Result<Self, Error> {
+        let kdf = KdfParams::default();
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(passphrase, &salt, kdf)?;
+        Ok(SealingKey { kdf, salt, key })
+    }
+
+    /// Derive key matching the salt and params of `sealed`
+    pub fn from_sealed(passphrase: &Secret, sealed: &Sealed) -> Result<Self, Error> {
+        let salt = decode(&sealed.salt)?;
+        let key = derive_key(passphrase, &salt, sealed.kdf)?;
+        Ok(SealingKey {
+            kdf: sealed.kdf,
+            salt,
+            key,
+        })
+    }
+
+    pub fn seal(&self, data: &[u8]) -> Result<Sealed, Error> {
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&self.key));
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| Error::Auth("Failed to encrypt data".to_string()))?;
+
+        Ok(Sealed {
+            kdf: self.kdf,
+            salt: STANDARD.encode(&self.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    pub fn open(&self, sealed: &Sealed) -> Result<Vec<u8>, Error> {
+        let nonce = decode(&sealed.nonce)?;
+        if nonce.len() != 24 {
+            return Err(Error::Auth("Invalid encrypted data: bad nonce".to_string()));
+        }
+        let ciphertext = decode(&sealed.ciphertext)?;
+
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&self.key));
+        // Неверный пароль и поврежденные данные не различить
+        cipher
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| Error::Auth("Wrong passphrase or corrupted data".to_string()))
+    }
+}
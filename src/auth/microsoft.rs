@@ -0,0 +1,581 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{AuthInfo, Secret};
+use crate::{
+    helpers,
+    internal_types::shared_auth::{
+        _JsonProfileSkin, JsonDeviceCode, JsonEntitlements, JsonMinecraftProfile,
+        JsonMinecraftToken, JsonOAuthError, JsonOAuthToken, JsonXboxError, JsonXboxToken,
+    },
+    types::Error,
+    utils,
+};
+
+const LOGIN_URL: &str = "https://login.microsoftonline.com/consumers";
+const XBOX_USER_URL: &str = "https://user.auth.xboxlive.com";
+const XSTS_URL: &str = "https://xsts.auth.xboxlive.com";
+const MINECRAFT_SERVICES_URL: &str = "https://api.minecraftservices.com";
+
+const SCOPE: &str = "XboxLive.signin offline_access";
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Environment variable with the Azure application id of the launcher
+pub const CLIENT_ID_ENV: &str = "LAUNCHER_MS_CLIENT_ID";
+
+/// Lifetime of Microsoft refresh tokens
+const REFRESH_TOKEN_LIFETIME: u64 = 90 * 24 * 60 * 60;
+
+pub type DeviceCode = JsonDeviceCode;
+pub type ProfileSkin = _JsonProfileSkin;
+
+/// Microsoft, Xbox Live and Minecraft Services endpoints
+///
+/// Base urls can be replaced, e.g. with a local mock server
+#[derive(Debug, Clone)]
+pub struct MicrosoftAuth {
+    /// Azure application id
+    pub client_id: String,
+    pub login_url: String,
+    pub xbox_user_url: String,
+    pub xsts_url: String,
+    pub minecraft_services_url: String,
+}
+
+/// Minecraft account authenticated with Microsoft
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MicrosoftAccount {
+    pub name: String,
+    pub uuid: Uuid,
+    pub xuid: String,
+    pub skins: Vec<ProfileSkin>,
+    /// Minecraft Services token used to launch the game
+    pub access_token: Secret,
+    /// Seconds since the unix epoch when `access_token` expires
+    pub expires_at: u64,
+    /// Microsoft OAuth refresh token
+    pub refresh_token: Secret,
+    /// Seconds since the unix epoch when `refresh_token` expires
+    ///
+    /// Microsoft does not report it, so the documented lifetime is assumed
+    #[serde(default)]
+    pub refresh_expires_at: u64,
+}
+
+impl MicrosoftAccount {
+    /// Check if `access_token` expires within `margin` seconds
+    pub fn is_token_expiring(&self, margin: u64) -> bool {
+        utils::get_timestamp() + margin >= self.expires_at
+    }
+
+    /// Check if `refresh_token` can't be used anymore
+    pub fn is_refresh_expired(&self) -> bool {
+        self.refresh_expires_at != 0 && utils::get_timestamp() >= self.refresh_expires_at
+    }
+
+    pub fn get_auth_info(&self) -> AuthInfo {
+        AuthInfo {
+            name: self.name.clone(),
+            uuid: self.uuid,
+            access_token: self.access_token.clone(),
+            user_type: "msa".to_string(),
+            xuid: self.xuid.clone(),
+            jvm_args: Vec::new(),
+        }
+    }
+}
+
+/// Convert unsuccessful response of the `step` into an error
+fn check_response(step: &str, status: StatusCode, body: &[u8]) -> Result<(), Error> {
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let details = if let Ok(error) = serde_json::from_slice::<JsonOAuthError>(body) {
+        format!("{}: {}", error.error, error.error_description)
+    } else if let Ok(error) = serde_json::from_slice::<JsonXboxError>(body) {
+        let reason = match error.xerr {
+            2148916233 => "the account has no Xbox profile",
+            2148916235 => "Xbox Live is not available in the account country",
+            2148916236 | 2148916237 => "the account needs adult verification",
+            2148916238 => "the account is a child account and must be added to a family",
+            _ => &error.message,
+        };
+        format!("XErr {}: {reason}", error.xerr)
+    } else {
+        String::from_utf8_lossy(body).to_string()
+    };
+
+    Err(Error::Auth(format!(
+        "{step} failed with {status}: {details}"
+    )))
+}
+
+impl MicrosoftAuth {
+    pub fn new(client_id: &str) -> Self {
+        MicrosoftAuth {
+            client_id: client_id.to_string(),
+            login_url: LOGIN_URL.to_string(),
+            xbox_user_url: XBOX_USER_URL.to_string(),
+            xsts_url: XSTS_URL.to_string(),
+            minecraft_services_url: MINECRAFT_SERVICES_URL.to_string(),
+        }
+    }
+
+    /// Auth with the application id from [`CLIENT_ID_ENV`], if it is set
+    pub fn from_env() -> Option<Self> {
+        let client_id = std::env::var(CLIENT_ID_ENV).ok()?;
+        Some(MicrosoftAuth::new(&client_id))
+    }
+
+    /// Start device code flow, the user has to open `verification_uri` and enter `user_code`
+    pub async fn request_device_code(&self, client: &reqwest::Client) -> Result<DeviceCode, Error> {
+        let url = format!("{}/oauth2/v2.0/devicecode", self.login_url);
+        let form = [("client_id", self.client_id.as_str()), ("scope", SCOPE)];
+        let (status, body) = helpers::http::post_form(&url, &form, client).await?;
+        check_response("Device code request", status, &body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Poll token endpoint until the user completes the device code login
+    async fn poll_device_token(
+        &self,
+        code: &DeviceCode,
+        client: &reqwest::Client,
+    ) -> Result<JsonOAuthToken, Error> {
+        let url = format!("{}/oauth2/v2.0/token", self.login_url);
+        let form = [
+            ("client_id", self.client_id.as_str()),
+            ("grant_type", DEVICE_CODE_GRANT),
+            ("device_code", code.device_code.as_str()),
+        ];
+
+        let deadline = utils::get_timestamp() + code.expires_in;
+        let mut interval = code.interval.max(1);
+
+        while utils::get_timestamp() < deadline {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+
+            let (status, body) = helpers::http::post_form(&url, &form, client).await?;
+            if status.is_success() {
+                return Ok(serde_json::from_slice(&body)?);
+            }
+
+            match serde_json::from_slice::<JsonOAuthError>(&body) {
+                Ok(error) if error.error == "authorization_pending" => {}
+                Ok(error) if error.error == "slow_down" => interval += 5,
+                _ => check_response("Device code login", status, &body)?,
+            }
+        }
+
+        Err(Error::Auth("Device code expired".to_string()))
+    }
+
+    async fn refresh_ms_token(
+        &self,
+        refresh_token: &str,
+        client: &reqwest::Client,
+    ) -> Result<JsonOAuthToken, Error> {
+        let url = format!("{}/oauth2/v2.0/token", self.login_url);
+        let form = [
+            ("client_id", self.client_id.as_str()),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("scope", SCOPE),
+        ];
+        let (status, body) = helpers::http::post_form(&url, &form, client).await?;
+        if let Ok(error) = serde_json::from_slice::<JsonOAuthError>(&body)
+            && error.error == "invalid_grant"
+        {
+            return Err(Error::Auth(
+                "Refresh token is expired or revoked, log in again".to_string(),
+            ));
+        }
+        check_response("Token refresh", status, &body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    async fn get_xbox_token(
+        &self,
+        ms_token: &str,
+        client: &reqwest::Client,
+    ) -> Result<JsonXboxToken, Error> {
+        let url = format!("{}/user/authenticate", self.xbox_user_url);
+        let body = json!({
+            "Properties": {
+                "AuthMethod": "RPS",
+                "SiteName": "user.auth.xboxlive.com",
+                "RpsTicket": format!("d={ms_token}"),
+            },
+            "RelyingParty": "http://auth.xboxlive.com",
+            "TokenType": "JWT",
+        });
+        let (status, body) = helpers::http::post_json(&url, &body, client).await?;
+        check_response("Xbox Live authentication", status, &body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    async fn get_xsts_token(
+        &self,
+        xbox_token: &str,
+        client: &reqwest::Client,
+    ) -> Result<JsonXboxToken, Error> {
+        let url = format!("{}/xsts/authorize", self.xsts_url);
+        let body = json!({
+            "Properties": {
+                "SandboxId": "RETAIL",
+                "UserTokens": [xbox_token],
+            },
+            "RelyingParty": "rp://api.minecraftservices.com/",
+            "TokenType": "JWT",
+        });
+        let (status, body) = helpers::http::post_json(&url, &body, client).await?;
+        check_response("XSTS authorization", status, &body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    async fn get_minecraft_token(
+        &self,
+        xsts: &JsonXboxToken,
+        client: &reqwest::Client,
+    ) -> Result<JsonMinecraftToken, Error> {
+        let uhs = &xsts
+            .display_claims
+            .xui
+            .first()
+            .ok_or_else(|| Error::Auth("XSTS token has no user hash".to_string()))?
+            .uhs;
+
+        let url = format!(
+            "{}/authentication/login_with_xbox",
+            self.minecraft_services_url
+        );
+        let body = json!({ "identityToken": format!("XBL3.0 x={uhs};{}", xsts.token) });
+        let (status, body) = helpers::http::post_json(&url, &body, client).await?;
+        check_response("Minecraft authentication", status, &body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Check that the account owns the game
+    pub async fn check_ownership(
+        &self,
+        access_token: &str,
+        client: &reqwest::Client,
+    ) -> Result<bool, Error> {
+        let url = format!("{}/entitlements/mcstore", self.minecraft_services_url);
+        let (status, body) = helpers::http::get_authorized(&url, access_token, client).await?;
+        check_response("Entitlements request", status, &body)?;
+
+        let entitlements = serde_json::from_slice::<JsonEntitlements>(&body)?;
+        Ok(entitlements
+            .items
+            .iter()
+            .any(|item| item.name == "product_minecraft" || item.name == "game_minecraft"))
+    }
+
+    pub async fn get_profile(
+        &self,
+        access_token: &str,
+        client: &reqwest::Client,
+    ) -> Result<JsonMinecraftProfile, Error> {
+        let url = format!("{}/minecraft/profile", self.minecraft_services_url);
+        let (status, body) = helpers::http::get_authorized(&url, access_token, client).await?;
+        if status == StatusCode::NOT_FOUND {
+            return Err(Error::Auth(
+                "The account has no Minecraft profile".to_string(),
+            ));
+        }
+        check_response("Profile request", status, &body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Exchange Microsoft token for the Minecraft account
+    async fn authenticate(
+        &self,
+        ms_token: JsonOAuthToken,
+        refresh_token: &str,
+        client: &reqwest::Client,
+    ) -> Result<MicrosoftAccount, Error> {
+        log::info!("Authenticating with Xbox Live...");
+        let xbox = self.get_xbox_token(&ms_token.access_token, client).await?;
+        let xsts = self.get_xsts_token(&xbox.token, client).await?;
+
+        log::info!("Authenticating with Minecraft Services...");
+        let minecraft = self.get_minecraft_token(&xsts, client).await?;
+
+        if !self
+            .check_ownership(&minecraft.access_token, client)
+            .await?
+        {
+            return Err(Error::Auth("The account does not own the game".to_string()));
+        }
+
+        let profile = self.get_profile(&minecraft.access_token, client).await?;
+        let uuid = Uuid::parse_str(&profile.id)
+            .map_err(|err| Error::Auth(format!("Invalid profile uuid: {err}")))?;
+        log::info!("Logged in as {}", profile.name);
+
+        Ok(MicrosoftAccount {
+            name: profile.name,
+            uuid,
+            xuid: xsts
+                .display_claims
+                .xui
+                .first()
+                .and_then(|claim| claim.xid.clone())
+                .unwrap_or_default(),
+            skins: profile.skins,
+            access_token: Secret::new(minecraft.access_token),
+            expires_at: utils::get_timestamp() + minecraft.expires_in,
+            // Новый refresh token выдается не всегда
+            refresh_token: Secret::new(
+                ms_token
+                    .refresh_token
+                    .unwrap_or_else(|| refresh_token.to_string()),
+            ),
+            refresh_expires_at: utils::get_timestamp() + REFRESH_TOKEN_LIFETIME,
+        })
+    }
+
+    /// Log in with the device code flow
+    ///
+    /// `on_code` is called once the code is received, to show it to the user
+    pub async fn login(
+        &self,
+        on_code: impl FnOnce(&DeviceCode),
+    ) -> Result<MicrosoftAccount, Error> {
+        let client = reqwest::Client::builder().build()?;
+
+        let code = self.request_device_code(&client).await?;
+        on_code(&code);
+
+        let ms_token = self.poll_device_token(&code, &client).await?;
+        self.authenticate(ms_token, "", &client).await
+    }
+
+    /// Get new tokens of the account with its refresh token
+    pub async fn refresh(&self, account: &MicrosoftAccount) -> Result<MicrosoftAccount, Error> {
+        log::info!("Refreshing account {}...", account.name);
+        let client = reqwest::Client::builder().build()?;
+
+        let ms_token = self
+            .refresh_ms_token(account.refresh_token.expose(), &client)
+            .await?;
+        self.authenticate(ms_token, account.refresh_token.expose(), &client)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::MockServer;
+
+    const PROFILE_ID: &str = "069a79f444e94726a5befca90e38aaf5";
+
+    fn get_auth(server: &MockServer) -> MicrosoftAuth {
+        MicrosoftAuth {
+            client_id: "client".to_string(),
+            login_url: format!("{}/login", server.url),
+            xbox_user_url: format!("{}/xbox", server.url),
+            xsts_url: format!("{}/xsts", server.url),
+            minecraft_services_url: format!("{}/minecraft", server.url),
+        }
+    }
+
+    /// Body of the last request on `path`
+    fn get_body(server: &MockServer, path: &str) -> Vec<u8> {
+        server
+            .requests()
+            .into_iter()
+            .rfind(|i| i.target == path)
+            .unwrap()
+            .body
+    }
+
+    /// Successful responses of every step of the login
+    fn route_login(server: &MockServer) {
+        server.route_json(
+            "/login/oauth2/v2.0/devicecode",
+            json!({
+                "user_code": "ABCD-EFGH",
+                "device_code": "device",
+                "verification_uri": "https://www.microsoft.com/link",
+                "expires_in": 900,
+                "interval": 0,
+            }),
+        );
+        server.route_json(
+            "/login/oauth2/v2.0/token",
+            json!({ "access_token": "ms-token", "refresh_token": "refresh", "expires_in": 3600 }),
+        );
+        server.route_json(
+            "/xbox/user/authenticate",
+            json!({ "Token": "xbox-token", "DisplayClaims": { "xui": [{ "uhs": "hash" }] } }),
+        );
+        server.route_json(
+            "/xsts/xsts/authorize",
+            json!({
+                "Token": "xsts-token",
+                "DisplayClaims": { "xui": [{ "uhs": "hash", "xid": "2535" }] },
+            }),
+        );
+        server.route_json(
+            "/minecraft/authentication/login_with_xbox",
+            json!({ "access_token": "mc-token", "expires_in": 86400 }),
+        );
+        server.route_json(
+            "/minecraft/entitlements/mcstore",
+            json!({ "items": [{ "name": "product_minecraft" }, { "name": "game_minecraft" }] }),
+        );
+        server.route_json(
+            "/minecraft/minecraft/profile",
+            json!({
+                "id": PROFILE_ID,
+                "name": "Notch",
+                "skins": [{ "id": "1", "state": "ACTIVE", "url": "https://textures", "variant": "CLASSIC" }],
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn device_code_login_builds_account() {
+        let server = MockServer::start().await;
+        route_login(&server);
+
+        let mut user_code = None;
+        let account = get_auth(&server)
+            .login(|code| user_code = Some(code.user_code.clone()))
+            .await
+            .unwrap();
+        assert_eq!(user_code.as_deref(), Some("ABCD-EFGH"));
+        assert_eq!(account.name, "Notch");
+        assert_eq!(account.uuid, Uuid::parse_str(PROFILE_ID).unwrap());
+        assert_eq!(account.xuid, "2535");
+        assert_eq!(account.skins[0].variant, "CLASSIC");
+        assert_eq!(account.access_token.expose(), "mc-token");
+        assert_eq!(account.refresh_token.expose(), "refresh");
+        assert!(!account.is_token_expiring(60));
+        assert!(!account.is_refresh_expired());
+
+        let token = String::from_utf8(get_body(&server, "/login/oauth2/v2.0/token")).unwrap();
+        assert!(token.contains("device_code=device"));
+        let xbox: serde_json::Value =
+            serde_json::from_slice(&get_body(&server, "/xbox/user/authenticate")).unwrap();
+        assert_eq!(xbox["Properties"]["RpsTicket"], "d=ms-token");
+        let xsts: serde_json::Value =
+            serde_json::from_slice(&get_body(&server, "/xsts/xsts/authorize")).unwrap();
+        assert_eq!(xsts["Properties"]["UserTokens"][0], "xbox-token");
+        let minecraft: serde_json::Value = serde_json::from_slice(&get_body(
+            &server,
+            "/minecraft/authentication/login_with_xbox",
+        ))
+        .unwrap();
+        assert_eq!(minecraft["identityToken"], "XBL3.0 x=hash;xsts-token");
+
+        let profile = server
+            .requests()
+            .into_iter()
+            .find(|i| i.target == "/minecraft/minecraft/profile")
+            .unwrap();
+        assert_eq!(profile.headers["authorization"], "Bearer mc-token");
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_old_refresh_token() {
+        let server = MockServer::start().await;
+        route_login(&server);
+        server.route_json(
+            "/login/oauth2/v2.0/token",
+            json!({ "access_token": "ms-token", "expires_in": 3600 }),
+        );
+        let auth = get_auth(&server);
+        let mut account = auth.login(|_| {}).await.unwrap();
+        account.refresh_token = Secret::new("old");
+
+        let account = auth.refresh(&account).await.unwrap();
+        assert_eq!(account.refresh_token.expose(), "old");
+        let token = String::from_utf8(get_body(&server, "/login/oauth2/v2.0/token")).unwrap();
+        assert!(token.contains("grant_type=refresh_token"));
+
+        server.route(
+            "/login/oauth2/v2.0/token",
+            400,
+            json!({ "error": "invalid_grant", "error_description": "expired" }).to_string(),
+        );
+        let Err(Error::Auth(message)) = auth.refresh(&account).await else {
+            panic!("refresh with revoked token succeeded");
+        };
+        assert!(message.contains("log in again"));
+    }
+
+    #[tokio::test]
+    async fn account_without_game_is_rejected() {
+        let server = MockServer::start().await;
+        route_login(&server);
+        server.route_json("/minecraft/entitlements/mcstore", json!({ "items": [] }));
+        let result = get_auth(&server).login(|_| {}).await;
+        assert!(matches!(result, Err(Error::Auth(message)) if message.contains("does not own")));
+
+        route_login(&server);
+        server.route("/minecraft/minecraft/profile", 404, "");
+        let result = get_auth(&server).login(|_| {}).await;
+        assert!(
+            matches!(result, Err(Error::Auth(message)) if message.contains("no Minecraft profile"))
+        );
+    }
+
+    #[tokio::test]
+    async fn xsts_error_is_reported() {
+        let server = MockServer::start().await;
+        route_login(&server);
+        server.route(
+            "/xsts/xsts/authorize",
+            401,
+            json!({ "XErr": 2148916238u64, "Message": "" }).to_string(),
+        );
+        let Err(Error::Auth(message)) = get_auth(&server).login(|_| {}).await else {
+            panic!("login with XSTS error succeeded");
+        };
+        assert!(message.starts_with("XSTS authorization failed with 401"));
+        assert!(message.contains("child account"));
+        assert!(!server.was_requested("/minecraft/authentication/login_with_xbox"));
+    }
+
+    #[test]
+    fn xsts_error_codes_are_mapped() {
+        let cases = [
+            (2148916233u64, "no Xbox profile"),
+            (2148916235, "not available"),
+            (2148916236, "adult verification"),
+            (2148916237, "adult verification"),
+            (2148916238, "child account"),
+            (1, "Unknown error"),
+        ];
+        for (xerr, reason) in cases {
+            let body = json!({ "XErr": xerr, "Message": "Unknown error" }).to_string();
+            let Err(Error::Auth(message)) =
+                check_response("XSTS", StatusCode::UNAUTHORIZED, body.as_bytes())
+            else {
+                panic!("XErr {xerr} is not an error");
+            };
+            assert!(message.contains(&format!("XErr {xerr}: ")), "{message}");
+            assert!(message.contains(reason), "{message}");
+        }
+
+        let body = json!({ "error": "expired_token", "error_description": "Code expired" });
+        let result = check_response(
+            "Login",
+            StatusCode::BAD_REQUEST,
+            body.to_string().as_bytes(),
+        );
+        assert!(
+            matches!(result, Err(Error::Auth(message)) if message.ends_with("expired_token: Code expired"))
+        );
+        assert!(check_response("Login", StatusCode::OK, b"").is_ok());
+    }
+}
@@ -0,0 +1,80 @@
+pub mod encryption;
+pub mod microsoft;
+pub mod offline;
+pub mod store;
+pub mod yggdrasil;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use microsoft::MicrosoftAccount;
+use offline::OfflineAccount;
+use yggdrasil::YggdrasilAccount;
+
+/// Token or other credential, hidden from `Debug` output and logs
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Values of the `${auth_*}` placeholders
+#[derive(Debug, Clone)]
+pub struct AuthInfo {
+    pub name: String,
+    pub uuid: Uuid,
+    pub access_token: Secret,
+    /// `msa`, `mojang` or `legacy`
+    pub user_type: String,
+    pub xuid: String,
+    /// Extra jvm arguments required by the account, e.g. authlib-injector
+    pub jvm_args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Account {
+    Offline(OfflineAccount),
+    Microsoft(MicrosoftAccount),
+    Yggdrasil(YggdrasilAccount),
+}
+
+impl Account {
+    pub fn get_uuid(&self) -> Uuid {
+        match self {
+            Account::Offline(account) => account.uuid,
+            Account::Microsoft(account) => account.uuid,
+            Account::Yggdrasil(account) => account.uuid,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            Account::Offline(account) => &account.name,
+            Account::Microsoft(account) => &account.name,
+            Account::Yggdrasil(account) => &account.name,
+        }
+    }
+
+    pub fn get_auth_info(&self) -> AuthInfo {
+        match self {
+            Account::Offline(account) => account.get_auth_info(),
+            Account::Microsoft(account) => account.get_auth_info(),
+            Account::Yggdrasil(account) => account.get_auth_info(),
+        }
+    }
+}
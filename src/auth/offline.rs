@@ -0,0 +1,57 @@
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{AuthInfo, Secret};
+use crate::types::Error;
+
+/// Account without authentication, for offline-mode servers and LAN games
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineAccount {
+    pub name: String,
+    pub uuid: Uuid,
+}
+
+/// Check that `name` is a valid player name: 3-16 chars of `[A-Za-z0-9_]`
+pub fn validate_username(name: &str) -> Result<(), Error> {
+    if !(3..=16).contains(&name.len()) {
+        return Err(Error::Auth(format!(
+            "Username \"{name}\" must be from 3 to 16 characters long"
+        )));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(Error::Auth(format!(
+            "Username \"{name}\" may only contain letters, digits and underscores"
+        )));
+    }
+    Ok(())
+}
+
+/// Get uuid of the offline player the same way as vanilla servers do,
+/// i.e. `UUID.nameUUIDFromBytes("OfflinePlayer:<name>")`
+pub fn get_offline_uuid(name: &str) -> Uuid {
+    let hash = Md5::digest(format!("OfflinePlayer:{name}").as_bytes());
+    uuid::Builder::from_md5_bytes(hash.into()).into_uuid()
+}
+
+impl OfflineAccount {
+    pub fn new(name: &str) -> Result<Self, Error> {
+        validate_username(name)?;
+        Ok(OfflineAccount {
+            name: name.to_string(),
+            uuid: get_offline_uuid(name),
+        })
+    }
+
+    pub fn get_auth_info(&self) -> AuthInfo {
+        AuthInfo {
+            name: self.name.clone(),
+            uuid: self.uuid,
+            // Игра требует непустой токен даже без авторизации
+            access_token: Secret::new("0"),
+            user_type: "legacy".to_string(),
+            xuid: String::new(),
+            jvm_args: Vec::new(),
+        }
+    }
+}
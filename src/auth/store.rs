@@ -0,0 +1,501 @@
+use std::{io::Write, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    Account, AuthInfo, Secret,
+    encryption::{Sealed, SealingKey},
+    microsoft::{CLIENT_ID_ENV, MicrosoftAccount, MicrosoftAuth},
+    yggdrasil::{AuthlibInjector, YggdrasilAuth},
+};
+use crate::types::{Error, Launcher};
+
+const ACCOUNTS_FILE: &str = "accounts.json";
+
+/// Environment variable with the passphrase of the encrypted store
+///
+/// Allows to unlock the store without a prompt, e.g. on headless machines
+pub const PASSPHRASE_ENV: &str = "LAUNCHER_ACCOUNTS_PASSPHRASE";
+
+/// Access token is refreshed if it expires within this number of seconds
+const TOKEN_REFRESH_MARGIN: u64 = 5 * 60;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct StoreData {
+    selected: Option<Uuid>,
+    accounts: Vec<Account>,
+}
+
+/// Contents of `accounts.json`
+///
+/// Plain store keeps the data as is, encrypted store keeps it in `sealed`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoreFile {
+    #[serde(flatten)]
+    data: StoreData,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed: Option<Sealed>,
+}
+
+/// Accounts saved under the launcher directory
+#[derive(Debug, Default)]
+pub struct AccountStore {
+    path: PathBuf,
+    data: StoreData,
+    /// Set if the store is encrypted, but not unlocked yet
+    locked: Option<Sealed>,
+    /// Set if the store is encrypted and unlocked
+    key: Option<SealingKey>,
+}
+
+impl AccountStore {
+    /// Load `accounts.json`, empty store is returned if it doesn't exist
+    pub fn load(launcher: &Launcher) -> Result<Self, Error> {
+        let path = launcher.path.join(ACCOUNTS_FILE);
+        let file: StoreFile = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => StoreFile::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(AccountStore {
+            path,
+            data: file.data,
+            locked: file.sealed,
+            key: None,
+        })
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.locked.is_some() || self.key.is_some()
+    }
+
+    /// Check if the store is encrypted and has to be unlocked first
+    pub fn is_locked(&self) -> bool {
+        self.locked.is_some()
+    }
+
+    /// Decrypt accounts of the encrypted store
+    pub fn unlock(&mut self, passphrase: &Secret) -> Result<(), Error> {
+        let Some(sealed) = &self.locked else {
+            return Ok(());
+        };
+        let key = SealingKey::from_sealed(passphrase, sealed)?;
+        self.data = serde_json::from_slice(&key.open(sealed)?)?;
+        self.locked = None;
+        self.key = Some(key);
+        Ok(())
+    }
+
+    /// Unlock the store with the passphrase from [`PASSPHRASE_ENV`]
+    pub fn unlock_from_env(&mut self) -> Result<(), Error> {
+        if !self.is_locked() {
+            return Ok(());
+        }
+        let passphrase = std::env::var(PASSPHRASE_ENV).map_err(|_| {
+            Error::Auth(format!("Account store is encrypted, set {PASSPHRASE_ENV}"))
+        })?;
+        self.unlock(&Secret::new(passphrase))
+    }
+
+    fn check_unlocked(&self) -> Result<(), Error> {
+        match self.locked {
+            Some(_) => Err(Error::Auth("Account store is locked".to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Encrypt the store with `passphrase` and save it
+    ///
+    /// Also used to change passphrase of the encrypted store
+    pub fn encrypt(&mut self, passphrase: &Secret) -> Result<(), Error> {
+        self.check_unlocked()?;
+        log::info!("Encrypting account store...");
+        self.key = Some(SealingKey::new(passphrase)?);
+        self.save()
+    }
+
+    /// Store accounts as plain json again and save them
+    pub fn decrypt(&mut self) -> Result<(), Error> {
+        self.check_unlocked()?;
+        log::info!("Decrypting account store...");
+        self.key = None;
+        self.save()
+    }
+
+    /// Write the store atomically, readable only by the current user
+    pub fn save(&self) -> Result<(), Error> {
+        self.check_unlocked()?;
+        let file = match &self.key {
+            Some(key) => StoreFile {
+                data: StoreData::default(),
+                sealed: Some(key.seal(&serde_json::to_vec(&self.data)?)?),
+            },
+            None => StoreFile {
+                data: StoreData {
+                    selected: self.data.selected,
+                    accounts: self.data.accounts.clone(),
+                },
+                sealed: None,
+            },
+        };
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let tmp_path = self.path.with_extension("json.tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.create(true).truncate(true).write(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut tmp_file = options.open(&tmp_path)?;
+        tmp_file.write_all(&serde_json::to_vec_pretty(&file)?)?;
+        tmp_file.sync_all()?;
+        drop(tmp_file);
+
+        std::fs::rename(tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> &[Account] {
+        &self.data.accounts
+    }
+
+    pub fn get(&self, uuid: Uuid) -> Option<&Account> {
+        self.data.accounts.iter().find(|i| i.get_uuid() == uuid)
+    }
+
+    /// Add `account` or replace account with the same uuid
+    ///
+    /// The first added account becomes selected
+    pub fn add(&mut self, account: Account) {
+        let uuid = account.get_uuid();
+        match self.data.accounts.iter_mut().find(|i| i.get_uuid() == uuid) {
+            Some(existing) => *existing = account,
+            None => self.data.accounts.push(account),
+        }
+        if self.data.selected.is_none() {
+            self.data.selected = Some(uuid);
+        }
+    }
+
+    pub fn remove(&mut self, uuid: Uuid) -> Option<Account> {
+        let index = self
+            .data
+            .accounts
+            .iter()
+            .position(|i| i.get_uuid() == uuid)?;
+        if self.data.selected == Some(uuid) {
+            self.data.selected = None;
+        }
+        Some(self.data.accounts.remove(index))
+    }
+
+    pub fn select(&mut self, uuid: Uuid) -> Result<(), Error> {
+        if self.get(uuid).is_none() {
+            return Err(Error::Auth(format!("Account {uuid} not found")));
+        }
+        self.data.selected = Some(uuid);
+        Ok(())
+    }
+
+    pub fn get_selected(&self) -> Option<&Account> {
+        self.get(self.data.selected?)
+    }
+
+    /// Accounts which can't be refreshed anymore and need a new login
+    pub fn get_expired(&self) -> Vec<&Account> {
+        self.data
+            .accounts
+            .iter()
+            .filter(|account| match account {
+                Account::Microsoft(account) => account.is_refresh_expired(),
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Get auth info of the selected account, refreshing its token if needed
+    ///
+    /// Refreshed account is saved right away
+    pub async fn prepare_launch(
+        &mut self,
+        launcher: &Launcher,
+        ms_auth: Option<&MicrosoftAuth>,
+        injector: &AuthlibInjector,
+    ) -> Result<AuthInfo, Error> {
+        let Some(account) = self.get_selected() else {
+            return Err(Error::Auth("No account selected".to_string()));
+        };
+
+        let refreshed = match account {
+            Account::Offline(_) => return Ok(account.get_auth_info()),
+            Account::Microsoft(account) => {
+                if !account.is_token_expiring(TOKEN_REFRESH_MARGIN) {
+                    return Ok(account.get_auth_info());
+                }
+                Account::Microsoft(refresh_microsoft(account, ms_auth).await?)
+            }
+            Account::Yggdrasil(account) => {
+                let auth = YggdrasilAuth::new(&account.api_root);
+                // Только отозванный токен требует нового входа, остальные ошибки отдаются как есть
+                let refreshed = match auth.validate(account).await? {
+                    true => None,
+                    false => match auth.refresh(account).await? {
+                        Some(refreshed) => Some(refreshed),
+                        None => {
+                            return Err(Error::Auth(format!(
+                                "Account {} is expired or revoked, log in again",
+                                account.name
+                            )));
+                        }
+                    },
+                };
+
+                let mut info = account.get_auth_info();
+                info.jvm_args = injector.get_jvm_args(launcher, &auth).await?;
+                let Some(refreshed) = refreshed else {
+                    return Ok(info);
+                };
+                info.access_token = refreshed.access_token.clone();
+                self.add(Account::Yggdrasil(refreshed));
+                self.save()?;
+                return Ok(info);
+            }
+        };
+
+        let auth = refreshed.get_auth_info();
+        self.add(refreshed);
+        self.save()?;
+
+        Ok(auth)
+    }
+}
+
+async fn refresh_microsoft(
+    account: &MicrosoftAccount,
+    ms_auth: Option<&MicrosoftAuth>,
+) -> Result<MicrosoftAccount, Error> {
+    if account.is_refresh_expired() {
+        log::warn!("Account {} is expired, log in again", account.name);
+        return Err(Error::Auth(format!(
+            "Account {} is expired, log in again",
+            account.name
+        )));
+    }
+    let Some(ms_auth) = ms_auth else {
+        return Err(Error::Auth(format!(
+            "Token of account {} is expired, set {CLIENT_ID_ENV} to refresh it",
+            account.name
+        )));
+    };
+
+    log::info!("Refreshing token of account {}...", account.name);
+    ms_auth.refresh(account).await.inspect_err(|err| {
+        log::warn!("Failed to refresh account {}: {err:?}", account.name);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        auth::{offline::OfflineAccount, yggdrasil::YggdrasilAccount},
+        helpers::testing::{MockServer, TempLauncher},
+        utils,
+    };
+
+    const PROFILE_ID: &str = "069a79f444e94726a5befca90e38aaf5";
+
+    fn get_auth(server: &MockServer) -> MicrosoftAuth {
+        MicrosoftAuth {
+            client_id: "client".to_string(),
+            login_url: format!("{}/login", server.url),
+            xbox_user_url: format!("{}/xbox", server.url),
+            xsts_url: format!("{}/xsts", server.url),
+            minecraft_services_url: format!("{}/minecraft", server.url),
+        }
+    }
+
+    /// Successful responses of every step of the refresh
+    fn route_refresh(server: &MockServer) {
+        server.route_json(
+            "/login/oauth2/v2.0/token",
+            json!({ "access_token": "ms-token", "refresh_token": "new-refresh", "expires_in": 3600 }),
+        );
+        server.route_json(
+            "/xbox/user/authenticate",
+            json!({ "Token": "xbox-token", "DisplayClaims": { "xui": [{ "uhs": "hash" }] } }),
+        );
+        server.route_json(
+            "/xsts/xsts/authorize",
+            json!({ "Token": "xsts-token", "DisplayClaims": { "xui": [{ "uhs": "hash" }] } }),
+        );
+        server.route_json(
+            "/minecraft/authentication/login_with_xbox",
+            json!({ "access_token": "new-token", "expires_in": 86400 }),
+        );
+        server.route_json(
+            "/minecraft/entitlements/mcstore",
+            json!({ "items": [{ "name": "game_minecraft" }] }),
+        );
+        server.route_json(
+            "/minecraft/minecraft/profile",
+            json!({ "id": PROFILE_ID, "name": "Notch", "skins": [] }),
+        );
+    }
+
+    fn microsoft_account(expires_at: u64, refresh_expires_at: u64) -> Account {
+        Account::Microsoft(MicrosoftAccount {
+            name: "Notch".to_string(),
+            uuid: Uuid::parse_str(PROFILE_ID).unwrap(),
+            xuid: String::new(),
+            skins: Vec::new(),
+            access_token: Secret::new("old-token"),
+            expires_at,
+            refresh_token: Secret::new("refresh"),
+            refresh_expires_at,
+        })
+    }
+
+    #[test]
+    fn accounts_are_saved_and_loaded() {
+        let temp = TempLauncher::new();
+        let mut store = AccountStore::load(&temp.launcher).unwrap();
+        assert!(store.list().is_empty());
+
+        let first = Account::Offline(OfflineAccount::new("first").unwrap());
+        let second = Account::Offline(OfflineAccount::new("second").unwrap());
+        let second_uuid = second.get_uuid();
+        store.add(first);
+        store.add(second);
+        store.select(second_uuid).unwrap();
+        store.save().unwrap();
+
+        let store = AccountStore::load(&temp.launcher).unwrap();
+        assert!(!store.is_encrypted());
+        let names: Vec<&str> = store.list().iter().map(|i| i.get_name()).collect();
+        assert_eq!(names, ["first", "second"]);
+        assert_eq!(store.get_selected().unwrap().get_uuid(), second_uuid);
+        assert!(!temp.path().join("accounts.json.tmp").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn store_is_readable_only_by_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempLauncher::new();
+        let mut store = AccountStore::load(&temp.launcher).unwrap();
+        store.add(Account::Offline(OfflineAccount::new("name").unwrap()));
+        store.save().unwrap();
+
+        let metadata = std::fs::metadata(temp.path().join(ACCOUNTS_FILE)).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    }
+
+    #[tokio::test]
+    async fn token_is_refreshed_only_when_expiring() {
+        let server = MockServer::start().await;
+        route_refresh(&server);
+        let temp = TempLauncher::new();
+        let ms_auth = get_auth(&server);
+        let now = utils::get_timestamp();
+
+        let mut store = AccountStore::load(&temp.launcher).unwrap();
+        store.add(microsoft_account(now + 3600, now + 3600));
+        let info = store
+            .prepare_launch(&temp.launcher, Some(&ms_auth), &AuthlibInjector::default())
+            .await
+            .unwrap();
+        assert_eq!(info.access_token.expose(), "old-token");
+        assert!(server.requests().is_empty());
+
+        // Токен истекает в пределах запаса, поэтому обновляется и сохраняется
+        store.add(microsoft_account(now + 60, now + 3600));
+        let info = store
+            .prepare_launch(&temp.launcher, Some(&ms_auth), &AuthlibInjector::default())
+            .await
+            .unwrap();
+        assert_eq!(info.access_token.expose(), "new-token");
+        assert!(server.was_requested("/login/oauth2/v2.0/token"));
+
+        let store = AccountStore::load(&temp.launcher).unwrap();
+        let Some(Account::Microsoft(account)) = store.get_selected() else {
+            panic!("Microsoft account is not saved");
+        };
+        assert_eq!(account.access_token.expose(), "new-token");
+        assert_eq!(account.refresh_token.expose(), "new-refresh");
+    }
+
+    #[tokio::test]
+    async fn expired_refresh_token_needs_login() {
+        let server = MockServer::start().await;
+        route_refresh(&server);
+        let temp = TempLauncher::new();
+        let now = utils::get_timestamp();
+
+        let mut store = AccountStore::load(&temp.launcher).unwrap();
+        store.add(microsoft_account(now - 60, now - 1));
+        assert_eq!(store.get_expired().len(), 1);
+        let result = store
+            .prepare_launch(
+                &temp.launcher,
+                Some(&get_auth(&server)),
+                &AuthlibInjector::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::Auth(_))));
+        assert!(server.requests().is_empty());
+
+        // Без приложения Azure токен обновить нельзя
+        store.add(microsoft_account(now - 60, now + 3600));
+        let result = store
+            .prepare_launch(&temp.launcher, None, &AuthlibInjector::default())
+            .await;
+        assert!(matches!(result, Err(Error::Auth(_))));
+    }
+
+    #[tokio::test]
+    async fn yggdrasil_errors_are_passed_through() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let mut store = AccountStore::load(&temp.launcher).unwrap();
+        store.add(Account::Yggdrasil(YggdrasilAccount {
+            name: "Notch".to_string(),
+            uuid: Uuid::parse_str(PROFILE_ID).unwrap(),
+            api_root: server.url.clone(),
+            access_token: Secret::new("old-token"),
+            client_token: "client".to_string(),
+        }));
+        server.route("/authserver/validate", 403, "");
+
+        server.route("/authserver/refresh", 503, "Maintenance");
+        let result = store
+            .prepare_launch(&temp.launcher, None, &AuthlibInjector::default())
+            .await;
+        let Err(Error::Auth(message)) = result else {
+            panic!("Refresh error must be returned");
+        };
+        assert!(message.contains("503"), "{message}");
+
+        let rejected =
+            json!({ "error": "ForbiddenOperationException", "errorMessage": "Invalid token." });
+        server.route("/authserver/refresh", 403, rejected.to_string());
+        let result = store
+            .prepare_launch(&temp.launcher, None, &AuthlibInjector::default())
+            .await;
+        let Err(Error::Auth(message)) = result else {
+            panic!("Revoked token must need a new login");
+        };
+        assert!(message.contains("log in again"), "{message}");
+    }
+}
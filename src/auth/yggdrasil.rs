@@ -0,0 +1,473 @@
+use std::path::PathBuf;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{AuthInfo, Secret};
+use crate::{
+    helpers,
+    internal_types::shared_auth::{
+        _JsonYggdrasilProfile, JsonAuthlibInjectorArtifact, JsonYggdrasilAuth, JsonYggdrasilError,
+    },
+    types::{Error, Launcher},
+    utils,
+};
+
+/// Metadata of the latest authlib-injector build
+pub const AUTHLIB_INJECTOR_URL: &str = "https://authlib-injector.yushi.moe/artifact/latest.json";
+
+/// Yggdrasil-compatible auth server, e.g. `https://example.com/api/yggdrasil`
+#[derive(Debug, Clone)]
+pub struct YggdrasilAuth {
+    pub api_root: String,
+}
+
+/// Minecraft account of the third-party Yggdrasil server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YggdrasilAccount {
+    pub name: String,
+    pub uuid: Uuid,
+    pub api_root: String,
+    pub access_token: Secret,
+    pub client_token: String,
+}
+
+impl YggdrasilAccount {
+    pub fn get_auth_info(&self) -> AuthInfo {
+        AuthInfo {
+            name: self.name.clone(),
+            uuid: self.uuid,
+            access_token: self.access_token.clone(),
+            user_type: "mojang".to_string(),
+            xuid: String::new(),
+            jvm_args: Vec::new(),
+        }
+    }
+}
+
+/// Convert unsuccessful response of the `step` into an error
+fn check_response(step: &str, status: StatusCode, body: &[u8]) -> Result<(), Error> {
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let details = match serde_json::from_slice::<JsonYggdrasilError>(body) {
+        Ok(error) => format!("{}: {}", error.error, error.error_message),
+        Err(_) => String::from_utf8_lossy(body).to_string(),
+    };
+    Err(Error::Auth(format!(
+        "{step} failed with {status}: {details}"
+    )))
+}
+
+/// Check if the server rejected the token itself, not the request
+fn is_token_revoked(status: StatusCode, body: &[u8]) -> bool {
+    status == StatusCode::FORBIDDEN
+        || serde_json::from_slice::<JsonYggdrasilError>(body)
+            .is_ok_and(|error| error.error == "ForbiddenOperationException")
+}
+
+fn parse_profile(profile: &_JsonYggdrasilProfile) -> Result<Uuid, Error> {
+    Uuid::parse_str(&profile.id).map_err(|err| Error::Auth(format!("Invalid profile uuid: {err}")))
+}
+
+impl YggdrasilAuth {
+    pub fn new(api_root: &str) -> Self {
+        YggdrasilAuth {
+            api_root: api_root.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn get_url(&self, endpoint: &str) -> String {
+        format!("{}/authserver/{endpoint}", self.api_root)
+    }
+
+    /// Log in with username (or email) and password
+    ///
+    /// `profile` selects the profile by name if the user has several of them
+    pub async fn authenticate(
+        &self,
+        username: &str,
+        password: &Secret,
+        profile: Option<&str>,
+    ) -> Result<YggdrasilAccount, Error> {
+        let client = reqwest::Client::builder().build()?;
+        let client_token = Uuid::new_v4().simple().to_string();
+
+        log::info!("Authenticating with {}...", self.api_root);
+        let body = json!({
+            "agent": { "name": "Minecraft", "version": 1 },
+            "username": username,
+            "password": password.expose(),
+            "clientToken": client_token,
+            "requestUser": false,
+        });
+        let (status, body) =
+            helpers::http::post_json(&self.get_url("authenticate"), &body, &client).await?;
+        check_response("Yggdrasil authentication", status, &body)?;
+        let auth: JsonYggdrasilAuth = serde_json::from_slice(&body)?;
+
+        let selected = match (&auth.selected_profile, profile) {
+            (Some(selected), None) => selected.clone(),
+            (Some(selected), Some(name)) if selected.name == name => selected.clone(),
+            (_, Some(name)) => auth
+                .available_profiles
+                .iter()
+                .find(|i| i.name == name)
+                .cloned()
+                .ok_or_else(|| Error::Auth(format!("Profile {name} not found")))?,
+            (None, None) => match auth.available_profiles.as_slice() {
+                [profile] => profile.clone(),
+                [] => return Err(Error::Auth("The account has no profiles".to_string())),
+                profiles => {
+                    let names: Vec<&str> = profiles.iter().map(|i| i.name.as_str()).collect();
+                    return Err(Error::Auth(format!(
+                        "The account has several profiles, select one of: {}",
+                        names.join(", ")
+                    )));
+                }
+            },
+        };
+
+        let account = YggdrasilAccount {
+            name: selected.name.clone(),
+            uuid: parse_profile(&selected)?,
+            api_root: self.api_root.clone(),
+            access_token: Secret::new(auth.access_token),
+            client_token: auth.client_token,
+        };
+
+        // Токен без выбранного профиля нужно привязать к профилю через refresh
+        if auth.selected_profile.is_none_or(|i| i.id != selected.id) {
+            return self
+                .refresh_with_profile(&account, Some(&selected))
+                .await?
+                .ok_or_else(|| Error::Auth("Token of the new login is rejected".to_string()));
+        }
+
+        log::info!("Logged in as {}", account.name);
+        Ok(account)
+    }
+
+    /// Refresh the token, `None` is returned if the token is revoked
+    async fn refresh_with_profile(
+        &self,
+        account: &YggdrasilAccount,
+        profile: Option<&_JsonYggdrasilProfile>,
+    ) -> Result<Option<YggdrasilAccount>, Error> {
+        let client = reqwest::Client::builder().build()?;
+
+        let mut body = json!({
+            "accessToken": account.access_token.expose(),
+            "clientToken": account.client_token,
+            "requestUser": false,
+        });
+        if let Some(profile) = profile {
+            body["selectedProfile"] = json!(profile);
+        }
+        let (status, body) =
+            helpers::http::post_json(&self.get_url("refresh"), &body, &client).await?;
+        if is_token_revoked(status, &body) {
+            return Ok(None);
+        }
+        check_response("Yggdrasil token refresh", status, &body)?;
+        let auth: JsonYggdrasilAuth = serde_json::from_slice(&body)?;
+
+        let (name, uuid) = match &auth.selected_profile {
+            Some(profile) => (profile.name.clone(), parse_profile(profile)?),
+            None => (account.name.clone(), account.uuid),
+        };
+        log::info!("Logged in as {name}");
+
+        Ok(Some(YggdrasilAccount {
+            name,
+            uuid,
+            api_root: self.api_root.clone(),
+            access_token: Secret::new(auth.access_token),
+            client_token: auth.client_token,
+        }))
+    }
+
+    /// Get new access token, the old one is invalidated
+    ///
+    /// `None` is returned if the token is revoked and the account has to log in again
+    pub async fn refresh(
+        &self,
+        account: &YggdrasilAccount,
+    ) -> Result<Option<YggdrasilAccount>, Error> {
+        log::info!("Refreshing token of account {}...", account.name);
+        self.refresh_with_profile(account, None).await
+    }
+
+    /// Check if access token of the account can still be used
+    pub async fn validate(&self, account: &YggdrasilAccount) -> Result<bool, Error> {
+        let client = reqwest::Client::builder().build()?;
+        let body = json!({
+            "accessToken": account.access_token.expose(),
+            "clientToken": account.client_token,
+        });
+        let (status, body) =
+            helpers::http::post_json(&self.get_url("validate"), &body, &client).await?;
+        if is_token_revoked(status, &body) {
+            return Ok(false);
+        }
+        check_response("Yggdrasil token validation", status, &body)?;
+        Ok(true)
+    }
+
+    /// Invalidate access token of the account, e.g. on log out
+    pub async fn invalidate(&self, account: &YggdrasilAccount) -> Result<(), Error> {
+        let client = reqwest::Client::builder().build()?;
+        let body = json!({
+            "accessToken": account.access_token.expose(),
+            "clientToken": account.client_token,
+        });
+        let (status, body) =
+            helpers::http::post_json(&self.get_url("invalidate"), &body, &client).await?;
+        check_response("Yggdrasil token invalidation", status, &body)
+    }
+
+    /// Get base64 encoded response of the api root
+    ///
+    /// Passed to authlib-injector, so it doesn't have to request it on start
+    pub async fn get_metadata(&self) -> Result<String, Error> {
+        let data = helpers::http::get(&self.api_root, None).await?;
+        Ok(STANDARD.encode(data))
+    }
+}
+
+/// Java agent which redirects the game auth to the Yggdrasil server
+#[derive(Debug, Clone)]
+pub struct AuthlibInjector {
+    /// Url of the artifact metadata like [`AUTHLIB_INJECTOR_URL`],
+    /// or url of the jar itself
+    pub url: String,
+}
+
+impl Default for AuthlibInjector {
+    fn default() -> Self {
+        AuthlibInjector::new(AUTHLIB_INJECTOR_URL)
+    }
+}
+
+impl AuthlibInjector {
+    pub fn new(url: &str) -> Self {
+        AuthlibInjector {
+            url: url.to_string(),
+        }
+    }
+
+    /// Download authlib-injector jar if it is not downloaded yet
+    pub async fn install(&self, launcher: &Launcher) -> Result<PathBuf, Error> {
+        let dir = launcher.path.join("authlib-injector");
+        tokio::fs::create_dir_all(&dir).await?;
+
+        // Jar по прямой ссылке кэшируется по ссылке, чтобы ее смена скачивала новый
+        if self.url.ends_with(".jar") {
+            let key = &utils::get_sha1(self.url.as_bytes())[..16];
+            let path = dir.join(format!("authlib-injector-{key}.jar"));
+            if !path.exists() {
+                log::info!("Downloading authlib-injector...");
+                let data = helpers::http::get(&self.url, None).await?;
+                tokio::fs::write(&path, data).await?;
+            }
+            return Ok(path);
+        }
+
+        let data = helpers::http::get(&self.url, None).await?;
+        let artifact: JsonAuthlibInjectorArtifact = serde_json::from_slice(&data)?;
+        let path = dir.join(format!("authlib-injector-{}.jar", artifact.version));
+        if path.exists()
+            && utils::get_sha256(&tokio::fs::read(&path).await?) == artifact.checksums.sha256
+        {
+            return Ok(path);
+        }
+
+        log::info!("Downloading authlib-injector {}...", artifact.version);
+        let data = helpers::http::get(&artifact.download_url, None).await?;
+        if utils::get_sha256(&data) != artifact.checksums.sha256 {
+            return Err(Error::Auth(
+                "Checksum mismatch of the authlib-injector jar".to_string(),
+            ));
+        }
+        tokio::fs::write(&path, data).await?;
+
+        Ok(path)
+    }
+
+    /// Get jvm arguments which enable authlib-injector for the `auth` server
+    pub async fn get_jvm_args(
+        &self,
+        launcher: &Launcher,
+        auth: &YggdrasilAuth,
+    ) -> Result<Vec<String>, Error> {
+        let jar = std::path::absolute(self.install(launcher).await?)?;
+        let metadata = auth.get_metadata().await?;
+        Ok(vec![
+            format!("-javaagent:{}={}", jar.to_string_lossy(), auth.api_root),
+            format!("-Dauthlibinjector.yggdrasil.prefetched={metadata}"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::{MockServer, TempLauncher};
+
+    const PROFILE_ID: &str = "069a79f444e94726a5befca90e38aaf5";
+    const OTHER_PROFILE_ID: &str = "853c80ef3c3749fdaa49938b674adae6";
+
+    fn get_account(server: &MockServer) -> YggdrasilAccount {
+        YggdrasilAccount {
+            name: "Notch".to_string(),
+            uuid: Uuid::parse_str(PROFILE_ID).unwrap(),
+            api_root: server.url.clone(),
+            access_token: Secret::new("old-token"),
+            client_token: "client".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn login_selects_profile_by_name() {
+        let server = MockServer::start().await;
+        server.route_json(
+            "/authserver/authenticate",
+            json!({
+                "accessToken": "token",
+                "clientToken": "client",
+                "availableProfiles": [
+                    { "id": PROFILE_ID, "name": "Notch" },
+                    { "id": OTHER_PROFILE_ID, "name": "jeb_" },
+                ],
+            }),
+        );
+        server.route_json(
+            "/authserver/refresh",
+            json!({
+                "accessToken": "bound-token",
+                "clientToken": "client",
+                "selectedProfile": { "id": OTHER_PROFILE_ID, "name": "jeb_" },
+            }),
+        );
+        let auth = YggdrasilAuth::new(&format!("{}/", server.url));
+
+        let result = auth.authenticate("user", &Secret::new("pass"), None).await;
+        let Err(Error::Auth(message)) = result else {
+            panic!("Several profiles must be rejected without a name");
+        };
+        assert!(message.contains("Notch, jeb_"));
+
+        let account = auth
+            .authenticate("user", &Secret::new("pass"), Some("jeb_"))
+            .await
+            .unwrap();
+        assert_eq!(account.name, "jeb_");
+        assert_eq!(account.uuid, Uuid::parse_str(OTHER_PROFILE_ID).unwrap());
+        assert_eq!(account.access_token.expose(), "bound-token");
+        assert_eq!(account.api_root, server.url);
+
+        let request = server.requests().pop().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["selectedProfile"]["name"], "jeb_");
+    }
+
+    #[tokio::test]
+    async fn only_rejected_token_is_revoked() {
+        let server = MockServer::start().await;
+        let auth = YggdrasilAuth::new(&server.url);
+        let account = get_account(&server);
+
+        server.route("/authserver/validate", 204, "");
+        assert!(auth.validate(&account).await.unwrap());
+        server.route("/authserver/validate", 403, "");
+        assert!(!auth.validate(&account).await.unwrap());
+
+        let rejected = json!({
+            "error": "ForbiddenOperationException",
+            "errorMessage": "Invalid token.",
+        });
+        server.route("/authserver/refresh", 403, rejected.to_string());
+        assert!(auth.refresh(&account).await.unwrap().is_none());
+        server.route("/authserver/refresh", 400, rejected.to_string());
+        assert!(auth.refresh(&account).await.unwrap().is_none());
+
+        // Ошибки сервера не означают, что токен отозван
+        server.route("/authserver/refresh", 500, "Internal error");
+        let Err(Error::Auth(message)) = auth.refresh(&account).await else {
+            panic!("Server error must be returned");
+        };
+        assert!(message.contains("500"));
+
+        server.route_json(
+            "/authserver/refresh",
+            json!({ "accessToken": "new-token", "clientToken": "client" }),
+        );
+        let refreshed = auth.refresh(&account).await.unwrap().unwrap();
+        assert_eq!(refreshed.access_token.expose(), "new-token");
+        assert_eq!(refreshed.name, "Notch");
+    }
+
+    #[tokio::test]
+    async fn direct_jar_is_cached_by_url() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        server.route("/1.2.4/authlib-injector.jar", 200, "old");
+        server.route("/1.2.5/authlib-injector.jar", 200, "new");
+
+        let old = AuthlibInjector::new(&format!("{}/1.2.4/authlib-injector.jar", server.url));
+        let path = old.install(&temp.launcher).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        assert_eq!(old.install(&temp.launcher).await.unwrap(), path);
+        assert_eq!(server.requests().len(), 1);
+
+        let new = AuthlibInjector::new(&format!("{}/1.2.5/authlib-injector.jar", server.url));
+        let new_path = new.install(&temp.launcher).await.unwrap();
+        assert_ne!(new_path, path);
+        assert_eq!(std::fs::read(&new_path).unwrap(), b"new");
+    }
+
+    #[tokio::test]
+    async fn artifact_checksum_is_verified() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        server.route("/authlib-injector-1.2.5.jar", 200, "agent");
+        let artifact = |sha256: String| {
+            json!({
+                "version": "1.2.5",
+                "download_url": format!("{}/authlib-injector-1.2.5.jar", server.url),
+                "checksums": { "sha256": sha256 },
+            })
+        };
+        let injector = AuthlibInjector::new(&format!("{}/latest.json", server.url));
+
+        server.route_json("/latest.json", artifact("0".repeat(64)));
+        assert!(matches!(
+            injector.install(&temp.launcher).await,
+            Err(Error::Auth(_))
+        ));
+
+        server.route_json("/latest.json", artifact(utils::get_sha256(b"agent")));
+        let path = injector.install(&temp.launcher).await.unwrap();
+        assert!(path.ends_with("authlib-injector-1.2.5.jar"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"agent");
+
+        server.route("/", 200, "{}");
+        let auth = YggdrasilAuth::new(&server.url);
+        let args = injector.get_jvm_args(&temp.launcher, &auth).await.unwrap();
+        let jar = std::path::absolute(&path).unwrap();
+        assert_eq!(
+            args,
+            [
+                format!("-javaagent:{}={}", jar.to_string_lossy(), server.url),
+                format!(
+                    "-Dauthlibinjector.yggdrasil.prefetched={}",
+                    STANDARD.encode("{}")
+                ),
+            ]
+        );
+    }
+}
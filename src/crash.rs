@@ -0,0 +1,261 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    time::SystemTime,
+};
+
+use crate::{process::GameProcess, types::Error};
+
+/// Number of the last output lines kept in the crash summary
+pub const DEFAULT_CRASH_LINES: usize = 100;
+
+/// Best-effort guess of the crash reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrashKind {
+    OutOfMemory,
+    MissingNativeLibrary,
+    WrongJavaVersion,
+    /// The jvm refused to start with the launch arguments
+    InvalidJvmArguments,
+    ModCrash {
+        mod_id: Option<String>,
+    },
+    GpuDriver,
+    Unknown,
+}
+
+/// Evidence collected after abnormal game exit
+#[derive(Debug, Clone)]
+pub struct CrashSummary {
+    /// `None` if the process was terminated by a signal
+    pub exit_code: Option<i32>,
+    /// Newest `crash-reports/crash-*.txt` written during the session
+    pub crash_report: Option<PathBuf>,
+    /// `hs_err_pid*.log` files written during the session
+    pub jvm_crash_logs: Vec<PathBuf>,
+    pub last_lines: Vec<String>,
+    pub kind: CrashKind,
+}
+
+const OUT_OF_MEMORY_PATTERNS: &[&str] = &[
+    "java.lang.OutOfMemoryError",
+    "There is insufficient memory for the Java Runtime Environment",
+];
+
+const NATIVE_LIBRARY_PATTERNS: &[&str] = &[
+    "java.lang.UnsatisfiedLinkError",
+    "Failed to locate library",
+    "in java.library.path",
+    "Can't load library",
+];
+
+const JAVA_VERSION_PATTERNS: &[&str] = &[
+    "java.lang.UnsupportedClassVersionError",
+    "compiled by a more recent version of the Java Runtime",
+    "Unsupported class file major version",
+];
+
+const INVALID_JVM_ARGUMENTS_PATTERNS: &[&str] = &[
+    "Unrecognized VM option",
+    "Unrecognized option:",
+    "Improperly specified VM option",
+    "Invalid initial heap size",
+    "Invalid maximum heap size",
+];
+
+const GPU_DRIVER_PATTERNS: &[&str] = &[
+    // AMD
+    "atio6axx.dll",
+    "atioglxx.dll",
+    // Nvidia
+    "nvoglv32.dll",
+    "nvoglv64.dll",
+    "libnvidia-glcore",
+    // Intel
+    "ig4icd64.dll",
+    "ig7icd64.dll",
+    "ig75icd64.dll",
+    "ig9icd64.dll",
+    "igxelpicd64.dll",
+    // Mesa
+    "libGLX_mesa.so",
+    "_dri.so",
+    "The driver does not appear to support OpenGL",
+    "Pixel format not accelerated",
+];
+
+/// Get files in `dir` matching `prefix*suffix`, modified after `since`
+fn get_session_files(dir: &Path, prefix: &str, suffix: &str, since: SystemTime) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with(prefix) && name.ends_with(suffix)
+        })
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            (modified >= since).then(|| (modified, entry.path()))
+        })
+        .collect();
+
+    // Новые файлы в начале
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Find id of the mod suspected in the crash
+fn find_mod_id(text: &str) -> Option<Option<String>> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+
+    for (i, line) in lines.iter().enumerate() {
+        // Forge: "Suspected Mod:" / "Suspected Mods:" и строка вида "Name (modid), Version: 1.0"
+        if line.starts_with("Suspected Mod") {
+            let value = match line.split_once(':') {
+                Some((_, value)) if !value.trim().is_empty() => value.trim(),
+                _ => lines.get(i + 1).copied().unwrap_or_default(),
+            };
+            let mod_id = value
+                .split_once('(')
+                .and_then(|(_, rest)| rest.split_once(')'))
+                .map(|(id, _)| id.to_string());
+            if value != "NONE" && value != "None" {
+                return Some(mod_id);
+            }
+        }
+
+        // Forge: "-- MOD examplemod --"
+        if let Some(mod_id) = line
+            .strip_prefix("-- MOD ")
+            .and_then(|rest| rest.strip_suffix(" --"))
+        {
+            return Some(Some(mod_id.to_string()));
+        }
+
+        // Fabric / Quilt: "Mixin apply for mod examplemod failed ..."
+        if let Some((_, rest)) = line.split_once("Mixin apply for mod ") {
+            return Some(rest.split_whitespace().next().map(String::from));
+        }
+    }
+
+    let loader_errors = [
+        "net.fabricmc.loader.impl.FormattedException",
+        "org.quiltmc.loader.impl.FormattedException",
+        "net.minecraftforge.fml.ModLoadingException",
+        "net.neoforged.fml.ModLoadingException",
+    ];
+    if loader_errors.iter().any(|error| text.contains(error)) {
+        return Some(None);
+    }
+
+    None
+}
+
+/// Guess crash reason from the crash report, jvm crash logs and the game output
+pub fn classify_crash(text: &str) -> CrashKind {
+    let contains_any = |patterns: &[&str]| patterns.iter().any(|pattern| text.contains(pattern));
+
+    if contains_any(JAVA_VERSION_PATTERNS) {
+        CrashKind::WrongJavaVersion
+    } else if contains_any(INVALID_JVM_ARGUMENTS_PATTERNS) {
+        CrashKind::InvalidJvmArguments
+    } else if contains_any(NATIVE_LIBRARY_PATTERNS) {
+        CrashKind::MissingNativeLibrary
+    } else if contains_any(OUT_OF_MEMORY_PATTERNS) {
+        CrashKind::OutOfMemory
+    } else if contains_any(GPU_DRIVER_PATTERNS) {
+        CrashKind::GpuDriver
+    } else if let Some(mod_id) = find_mod_id(text) {
+        CrashKind::ModCrash { mod_id }
+    } else {
+        CrashKind::Unknown
+    }
+}
+
+/// Collect crash evidence of the session started at `started_at`
+///
+/// Crash reports are searched in `game_dir`, jvm crash logs in `working_dir` of the process.
+/// `None` is returned if the game exited normally and left no crash report
+pub async fn collect_crash(
+    game_dir: &Path,
+    working_dir: &Path,
+    started_at: SystemTime,
+    status: ExitStatus,
+    last_lines: Vec<String>,
+) -> Result<Option<CrashSummary>, Error> {
+    let crash_report = get_session_files(
+        &game_dir.join("crash-reports"),
+        "crash-",
+        ".txt",
+        started_at,
+    )
+    .into_iter()
+    .next();
+    let jvm_crash_logs = get_session_files(working_dir, "hs_err_pid", ".log", started_at);
+
+    if status.success() && crash_report.is_none() && jvm_crash_logs.is_empty() {
+        return Ok(None);
+    }
+
+    let mut text = last_lines.join("\n");
+    for path in crash_report.iter().chain(jvm_crash_logs.iter()) {
+        text.push('\n');
+        text.push_str(&String::from_utf8_lossy(&tokio::fs::read(path).await?));
+    }
+
+    let kind = classify_crash(&text);
+    log::warn!("Game crashed with {status}: {kind:?}");
+
+    Ok(Some(CrashSummary {
+        exit_code: status.code(),
+        crash_report,
+        jvm_crash_logs,
+        last_lines,
+        kind,
+    }))
+}
+
+/// Wait for the game process to exit and check it for a crash
+///
+/// Process stopped with [`GameProcess::kill`] is not reported as a crash
+pub async fn wait_for_crash(
+    process: &GameProcess,
+    game_dir: &Path,
+) -> Result<Option<CrashSummary>, Error> {
+    let status = process.wait().await?;
+    if process.was_killed() {
+        log::info!("Game process was killed");
+        return Ok(None);
+    }
+    let last_lines = process.last_lines(DEFAULT_CRASH_LINES);
+    collect_crash(
+        game_dir,
+        process.working_dir(),
+        process.started_at(),
+        status,
+        last_lines,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_jvm_arguments_are_not_java_version() {
+        let output = "Unrecognized VM option 'UseConcMarkSweepGC'\n\
+                      Error: Could not create the Java Virtual Machine.";
+        assert_eq!(classify_crash(output), CrashKind::InvalidJvmArguments);
+        let output = "Invalid maximum heap size: -Xmx64x";
+        assert_eq!(classify_crash(output), CrashKind::InvalidJvmArguments);
+
+        let output = "java.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main \
+                      has been compiled by a more recent version of the Java Runtime";
+        assert_eq!(classify_crash(output), CrashKind::WrongJavaVersion);
+    }
+}
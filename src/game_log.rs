@@ -0,0 +1,445 @@
+use std::fmt::Display;
+
+const EVENT_START: &str = "<log4j:Event";
+const EVENT_END: &str = "</log4j:Event>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    fn parse(value: &str) -> Option<Self> {
+        let level = match value.to_ascii_uppercase().as_str() {
+            "TRACE" => LogLevel::Trace,
+            "DEBUG" => LogLevel::Debug,
+            "INFO" => LogLevel::Info,
+            "WARN" | "WARNING" => LogLevel::Warn,
+            "ERROR" => LogLevel::Error,
+            "FATAL" => LogLevel::Fatal,
+            _ => return None,
+        };
+        Some(level)
+    }
+}
+
+impl From<LogLevel> for log::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => log::Level::Trace,
+            LogLevel::Debug => log::Level::Debug,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Error | LogLevel::Fatal => log::Level::Error,
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Fatal => "FATAL",
+        };
+        f.write_str(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogTime {
+    /// Milliseconds since the unix epoch, from the log4j xml events
+    Timestamp(u64),
+    /// Wall clock time of the plain lines, e.g. `[19:24:45]`
+    Clock { hour: u8, minute: u8, second: u8 },
+}
+
+/// Single log event of the game output
+///
+/// Lines that are not log events (e.g. stack traces printed directly to
+/// stderr) are kept as events with only the `message` set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEvent {
+    pub time: Option<LogTime>,
+    pub thread: Option<String>,
+    pub level: Option<LogLevel>,
+    pub logger: Option<String>,
+    pub message: String,
+    pub throwable: Option<String>,
+}
+
+impl LogEvent {
+    fn raw(line: &str) -> Self {
+        LogEvent {
+            time: None,
+            thread: None,
+            level: None,
+            logger: None,
+            message: line.to_string(),
+            throwable: None,
+        }
+    }
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&#13;", "\r")
+        .replace("&amp;", "&")
+}
+
+/// Get value of attribute `name` from the xml tag
+fn get_xml_attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {name}=\"");
+    let start = tag.find(&pattern)? + pattern.len();
+    let len = tag[start..].find('"')?;
+    Some(unescape_xml(&tag[start..start + len]))
+}
+
+/// Get text of the xml element `name`
+///
+/// log4j splits `]]>` inside of messages into several CDATA sections,
+/// so all of them are joined
+fn get_xml_element(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{name}>");
+    let close = format!("</{name}>");
+    let start = xml.find(&open)? + open.len();
+    let len = xml[start..].find(&close)?;
+    let mut content = &xml[start..start + len];
+
+    let mut text = String::new();
+    while !content.is_empty() {
+        match content.find("<![CDATA[") {
+            Some(0) => {
+                let end = content.find("]]>").unwrap_or(content.len());
+                text.push_str(&content[9..end]);
+                content = content.get(end + 3..).unwrap_or_default();
+            }
+            Some(cdata) => {
+                text.push_str(&unescape_xml(content[..cdata].trim()));
+                content = &content[cdata..];
+            }
+            None => {
+                text.push_str(&unescape_xml(content.trim()));
+                break;
+            }
+        }
+    }
+    Some(text)
+}
+
+/// Parse the whole `<log4j:Event>` element
+pub fn parse_xml_event(xml: &str) -> Option<LogEvent> {
+    let start = xml.find(EVENT_START)?;
+    let tag = &xml[start..start + xml[start..].find('>')?];
+
+    Some(LogEvent {
+        time: get_xml_attribute(tag, "timestamp")
+            .and_then(|time| time.parse().ok())
+            .map(LogTime::Timestamp),
+        thread: get_xml_attribute(tag, "thread"),
+        level: get_xml_attribute(tag, "level").and_then(|level| LogLevel::parse(&level)),
+        logger: get_xml_attribute(tag, "logger"),
+        message: get_xml_element(xml, "log4j:Message").unwrap_or_default(),
+        throwable: get_xml_element(xml, "log4j:Throwable"),
+    })
+}
+
+fn parse_clock(value: &str) -> Option<LogTime> {
+    let mut parts = value.split(':').map(|part| part.parse::<u8>().ok());
+    let (Some(Some(hour)), Some(Some(minute)), Some(Some(second)), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    Some(LogTime::Clock {
+        hour,
+        minute,
+        second,
+    })
+}
+
+/// Parse plain line, e.g. `[19:24:45] [Render thread/INFO]: Setting user: name`
+///
+/// Loggers in form of `[19:24:45] [main/INFO] [net.minecraft.Main/]: message`
+/// are supported as well
+pub fn parse_plain_line(line: &str) -> Option<LogEvent> {
+    let rest = line.strip_prefix('[')?;
+    let (time, rest) = rest.split_once("] [")?;
+    let time = parse_clock(time)?;
+
+    let (thread_level, rest) = rest.split_once(']')?;
+    let (thread, level) = thread_level.rsplit_once('/')?;
+    let level = LogLevel::parse(level)?;
+
+    let (logger, message) = match rest.strip_prefix(" [") {
+        Some(rest) => {
+            let (logger, message) = rest.split_once("]: ")?;
+            let logger = logger.trim_end_matches('/');
+            (Some(logger.to_string()), message)
+        }
+        None => (None, rest.strip_prefix(": ")?),
+    };
+
+    Some(LogEvent {
+        time: Some(time),
+        thread: Some(thread.to_string()),
+        level: Some(level),
+        logger,
+        message: message.to_string(),
+        throwable: None,
+    })
+}
+
+/// Check if the line is an exception header, e.g. `java.io.IOException: message`
+fn is_exception_line(line: &str) -> bool {
+    let class = line.split_once(':').map_or(line, |(class, _)| class);
+    !class.is_empty()
+        && class.contains('.')
+        && class
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '$' | '_'))
+        && (class.ends_with("Exception")
+            || class.ends_with("Error")
+            || class.ends_with("Throwable"))
+}
+
+/// Check if the line continues the stack trace of the previous event
+fn is_stack_trace_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let is_frame = trimmed.starts_with("at ") || trimmed.starts_with("... ");
+    (line.starts_with(['\t', ' ']) && is_frame)
+        || trimmed.starts_with("Caused by: ")
+        || trimmed.starts_with("Suppressed: ")
+        || is_exception_line(line)
+}
+
+/// Stateful parser of the game output
+///
+/// Xml events span several lines, so lines are buffered until the event is closed.
+/// Plain events are held until the next line, so stack trace lines following them
+/// are attached as their `throwable`. The last plain event of a burst is not returned
+/// by [`LogParser::push`] until more output comes, use [`LogParser::flush_held`]
+/// once the output is idle for a while
+#[derive(Debug, Default)]
+pub struct LogParser {
+    xml: Option<String>,
+    plain: Option<LogEvent>,
+}
+
+impl LogParser {
+    pub fn new() -> Self {
+        LogParser::default()
+    }
+
+    /// Push next output line, returns events once they are complete
+    pub fn push(&mut self, line: &str) -> Vec<LogEvent> {
+        if let Some(xml) = &mut self.xml {
+            xml.push_str(line);
+            xml.push('\n');
+            if !line.contains(EVENT_END) {
+                return Vec::new();
+            }
+            let xml = self.xml.take().unwrap_or_default();
+            return parse_xml_event(&xml).into_iter().collect();
+        }
+
+        if line.trim_start().starts_with(EVENT_START) {
+            let mut events: Vec<LogEvent> = self.plain.take().into_iter().collect();
+            if line.contains(EVENT_END) {
+                events.extend(parse_xml_event(line));
+            } else {
+                self.xml = Some(format!("{line}\n"));
+            }
+            return events;
+        }
+
+        if line.trim().is_empty() {
+            return Vec::new();
+        }
+
+        if is_stack_trace_line(line)
+            && let Some(event) = &mut self.plain
+        {
+            let throwable = event.throwable.get_or_insert_with(String::new);
+            if !throwable.is_empty() {
+                throwable.push('\n');
+            }
+            throwable.push_str(line);
+            return Vec::new();
+        }
+
+        let event = parse_plain_line(line).unwrap_or_else(|| LogEvent::raw(line));
+        self.plain.replace(event).into_iter().collect()
+    }
+
+    /// Check if a plain event is held waiting for its stack trace
+    pub fn is_holding(&self) -> bool {
+        self.plain.is_some()
+    }
+
+    /// Flush the held plain event, stack trace lines coming later are returned as raw events
+    pub fn flush_held(&mut self) -> Option<LogEvent> {
+        self.plain.take()
+    }
+
+    /// Flush the held plain event and unfinished xml event as a raw event
+    pub fn finish(&mut self) -> Vec<LogEvent> {
+        let xml = self.xml.take().map(|xml| LogEvent::raw(xml.trim_end()));
+        self.plain.take().into_iter().chain(xml).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_stack_trace_is_attached_to_event() {
+        let mut parser = LogParser::new();
+        let lines = [
+            "[19:24:45] [Render thread/ERROR]: Failed to load texture",
+            "java.io.FileNotFoundException: missing.png",
+            "\tat net.minecraft.Texture.load(Texture.java:42)",
+            "Caused by: java.io.IOException: closed",
+            "\t... 3 more",
+            "[19:24:46] [Render thread/INFO]: Done",
+        ];
+        let mut events: Vec<LogEvent> = lines.iter().flat_map(|i| parser.push(i)).collect();
+        events.extend(parser.finish());
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].message, "Failed to load texture");
+        assert_eq!(events[0].level, Some(LogLevel::Error));
+        assert_eq!(
+            events[0].throwable.as_deref(),
+            Some(
+                "java.io.FileNotFoundException: missing.png\n\
+                 \tat net.minecraft.Texture.load(Texture.java:42)\n\
+                 Caused by: java.io.IOException: closed\n\
+                 \t... 3 more"
+            )
+        );
+        assert_eq!(events[1].message, "Done");
+        assert_eq!(events[1].throwable, None);
+    }
+
+    #[test]
+    fn stack_trace_without_event_is_raw() {
+        let mut parser = LogParser::new();
+        let events = parser.push("\tat net.minecraft.Main.main(Main.java:1)");
+        assert!(events.is_empty());
+        let events = parser.finish();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].message,
+            "\tat net.minecraft.Main.main(Main.java:1)"
+        );
+    }
+
+    #[test]
+    fn xml_event_is_parsed() {
+        let xml = r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000123" level="WARN" thread="Render thread">
+  <log4j:Message><![CDATA[Setting user: name]]></log4j:Message>
+</log4j:Event>"#;
+        let event = parse_xml_event(xml).unwrap();
+
+        assert_eq!(event.time, Some(LogTime::Timestamp(1700000000123)));
+        assert_eq!(event.thread.as_deref(), Some("Render thread"));
+        assert_eq!(event.level, Some(LogLevel::Warn));
+        assert_eq!(
+            event.logger.as_deref(),
+            Some("net.minecraft.client.Minecraft")
+        );
+        assert_eq!(event.message, "Setting user: name");
+        assert_eq!(event.throwable, None);
+    }
+
+    #[test]
+    fn xml_event_is_buffered_until_closed() {
+        let mut parser = LogParser::new();
+        let lines = [
+            r#"<log4j:Event logger="Main" timestamp="1" level="ERROR" thread="main">"#,
+            "  <log4j:Message><![CDATA[Crashed]]></log4j:Message>",
+            "  <log4j:Throwable><![CDATA[java.lang.IllegalStateException: boom",
+            "\tat Main.main(Main.java:1)",
+            "]]></log4j:Throwable>",
+        ];
+        for line in lines {
+            assert!(parser.push(line).is_empty());
+        }
+
+        let events = parser.push("</log4j:Event>");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "Crashed");
+        assert_eq!(events[0].level, Some(LogLevel::Error));
+        assert_eq!(
+            events[0].throwable.as_deref(),
+            Some("java.lang.IllegalStateException: boom\n\tat Main.main(Main.java:1)\n")
+        );
+        assert!(parser.finish().is_empty());
+    }
+
+    #[test]
+    fn split_cdata_sections_are_joined() {
+        let xml = "<log4j:Message><![CDATA[a]]]]><![CDATA[>b]]></log4j:Message>";
+        assert_eq!(
+            get_xml_element(xml, "log4j:Message").as_deref(),
+            Some("a]]>b")
+        );
+    }
+
+    #[test]
+    fn ampersand_is_unescaped_last() {
+        assert_eq!(unescape_xml("&amp;lt;"), "&lt;");
+        assert_eq!(unescape_xml("&lt;&amp;&gt;"), "<&>");
+        let tag = r#"<log4j:Event thread="a &amp;quot;b&quot;""#;
+        assert_eq!(
+            get_xml_attribute(tag, "thread").as_deref(),
+            Some("a &quot;b\"")
+        );
+    }
+
+    #[test]
+    fn unfinished_xml_event_is_flushed_as_raw() {
+        let mut parser = LogParser::new();
+        assert!(parser.push("[19:24:45] [main/INFO]: Before").is_empty());
+
+        let events = parser.push(r#"<log4j:Event logger="Main" level="INFO" thread="main">"#);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "Before");
+
+        assert!(parser.push("  <log4j:Message><![CDATA[Cut").is_empty());
+        let events = parser.finish();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].level, None);
+        assert_eq!(
+            events[0].message,
+            "<log4j:Event logger=\"Main\" level=\"INFO\" thread=\"main\">\n  <log4j:Message><![CDATA[Cut"
+        );
+    }
+
+    #[test]
+    fn held_plain_event_is_flushed() {
+        let mut parser = LogParser::new();
+        assert!(parser.push("[19:24:45] [main/INFO]: Last line").is_empty());
+        assert!(parser.is_holding());
+
+        let event = parser.flush_held().unwrap();
+        assert_eq!(event.message, "Last line");
+        assert!(!parser.is_holding());
+        assert!(parser.finish().is_empty());
+    }
+}
@@ -1,8 +1,17 @@
 pub mod http {
+    use reqwest::{RequestBuilder, StatusCode, header::HeaderMap};
+    use serde::Serialize;
+
     use crate::types::Error;
 
     const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
+    fn get_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("User-Agent", USER_AGENT.parse().unwrap());
+        headers
+    }
+
     pub async fn get(url: &str, client: Option<&reqwest::Client>) -> Result<Vec<u8>, Error> {
         log::debug!("[GET] Request on {url}");
         let client = match client {
@@ -10,14 +19,214 @@ pub mod http {
             None => &reqwest::Client::builder().build()?,
         };
 
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert("User-Agent", USER_AGENT.parse().unwrap());
-
-        let request = client.request(reqwest::Method::GET, url).headers(headers);
-        let response = request.send().await?;
+        let request = client
+            .request(reqwest::Method::GET, url)
+            .headers(get_headers());
+        let response = request.send().await?.error_for_status()?;
 
         let body = response.bytes().await?.to_vec();
 
         Ok(body)
     }
+
+    /// Send request and return status with body, status is not checked
+    async fn send(request: RequestBuilder) -> Result<(StatusCode, Vec<u8>), Error> {
+        let response = request.headers(get_headers()).send().await?;
+        let status = response.status();
+        let body = response.bytes().await?.to_vec();
+        Ok((status, body))
+    }
+
+    /// GET request with `Authorization: Bearer <token>`
+    pub async fn get_authorized(
+        url: &str,
+        token: &str,
+        client: &reqwest::Client,
+    ) -> Result<(StatusCode, Vec<u8>), Error> {
+        log::debug!("[GET] Request on {url}");
+        send(client.get(url).bearer_auth(token)).await
+    }
+
+    pub async fn post_json<T: Serialize>(
+        url: &str,
+        body: &T,
+        client: &reqwest::Client,
+    ) -> Result<(StatusCode, Vec<u8>), Error> {
+        log::debug!("[POST] Request on {url}");
+        let request = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .body(serde_json::to_vec(body)?);
+        send(request).await
+    }
+
+    pub async fn post_form(
+        url: &str,
+        form: &[(&str, &str)],
+        client: &reqwest::Client,
+    ) -> Result<(StatusCode, Vec<u8>), Error> {
+        log::debug!("[POST] Request on {url}");
+        send(client.post(url).form(form)).await
+    }
+}
+
+/// Helpers for tests: temporary launcher directory and mock HTTP server
+#[cfg(test)]
+pub mod testing {
+    use std::{
+        collections::HashMap,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use crate::types::Launcher;
+
+    /// Launcher in a unique temporary directory, removed on drop
+    pub struct TempLauncher {
+        pub launcher: Launcher,
+    }
+
+    impl TempLauncher {
+        pub fn new() -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "minecraft-rs-test-{}",
+                uuid::Uuid::new_v4().simple()
+            ));
+            std::fs::create_dir_all(&path).unwrap();
+            TempLauncher {
+                launcher: Launcher { path },
+            }
+        }
+
+        pub fn path(&self) -> PathBuf {
+            self.launcher.path.clone()
+        }
+    }
+
+    impl Drop for TempLauncher {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.launcher.path);
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct MockRequest {
+        pub method: String,
+        /// Path with the query string
+        pub target: String,
+        /// Header names are lowercase
+        pub headers: HashMap<String, String>,
+        pub body: Vec<u8>,
+    }
+
+    /// Status and body of the response by request path
+    type Routes = Arc<Mutex<HashMap<String, (u16, Vec<u8>)>>>;
+
+    /// HTTP server answering by request path, the query string is ignored
+    ///
+    /// Unknown paths get 404
+    pub struct MockServer {
+        pub url: String,
+        routes: Routes,
+        requests: Arc<Mutex<Vec<MockRequest>>>,
+    }
+
+    impl MockServer {
+        pub async fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let routes: Routes = Arc::default();
+            let requests: Arc<Mutex<Vec<MockRequest>>> = Arc::default();
+
+            let (server_routes, server_requests) = (routes.clone(), requests.clone());
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let (routes, requests) = (server_routes.clone(), server_requests.clone());
+                    tokio::spawn(async move {
+                        let mut stream = BufReader::new(stream);
+                        let mut request_line = String::new();
+                        stream.read_line(&mut request_line).await?;
+                        let mut parts = request_line.split_whitespace();
+                        let method = parts.next().unwrap_or_default().to_string();
+                        let target = parts.next().unwrap_or_default().to_string();
+
+                        let mut headers = HashMap::new();
+                        loop {
+                            let mut line = String::new();
+                            stream.read_line(&mut line).await?;
+                            let Some((name, value)) = line.trim_end().split_once(':') else {
+                                break;
+                            };
+                            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+                        }
+                        let length = headers
+                            .get("content-length")
+                            .and_then(|i| i.parse().ok())
+                            .unwrap_or(0);
+                        let mut body = vec![0; length];
+                        stream.read_exact(&mut body).await?;
+
+                        let path = target.split('?').next().unwrap_or_default().to_string();
+                        let (status, response) = routes
+                            .lock()
+                            .unwrap()
+                            .get(&path)
+                            .cloned()
+                            .unwrap_or((404, Vec::new()));
+                        requests.lock().unwrap().push(MockRequest {
+                            method,
+                            target,
+                            headers,
+                            body,
+                        });
+
+                        let head = format!(
+                            "HTTP/1.1 {status} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            response.len()
+                        );
+                        let stream = stream.get_mut();
+                        stream.write_all(head.as_bytes()).await?;
+                        stream.write_all(&response).await?;
+                        stream.shutdown().await
+                    });
+                }
+            });
+
+            MockServer {
+                url,
+                routes,
+                requests,
+            }
+        }
+
+        /// Answer `path` with `status` and `body`
+        pub fn route(&self, path: &str, status: u16, body: impl Into<Vec<u8>>) {
+            self.routes
+                .lock()
+                .unwrap()
+                .insert(path.to_string(), (status, body.into()));
+        }
+
+        /// Answer `path` with 200 and `value` as json
+        pub fn route_json(&self, path: &str, value: serde_json::Value) {
+            self.route(path, 200, serde_json::to_vec(&value).unwrap());
+        }
+
+        pub fn requests(&self) -> Vec<MockRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        /// Check if `path` was requested, the query string is ignored
+        pub fn was_requested(&self, path: &str) -> bool {
+            self.requests()
+                .iter()
+                .any(|i| i.target.split('?').next() == Some(path))
+        }
+    }
 }
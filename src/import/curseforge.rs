@@ -0,0 +1,521 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use zip::ZipArchive;
+
+use super::{extract_zip_dir, sanitize_instance_name};
+use crate::{
+    install,
+    instances::{self, Instance, Loader, LoaderKind},
+    internal_types::shared_curseforge::{
+        JsonCurseForgeFile, JsonCurseForgeManifest, JsonCurseForgeMod,
+    },
+    mods::{self, FileHashes, curseforge::CurseForgeApi},
+    types::{Error, Launcher},
+};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const DEFAULT_OVERRIDES_DIR: &str = "overrides";
+
+const WORLD_CLASS_ID: u64 = 17;
+
+/// Why the file was not installed automatically
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManualReason {
+    /// The author disallowed third-party downloads
+    DownloadDisallowed,
+    /// The api doesn't know the file, e.g. it was deleted
+    FileNotFound,
+    /// Worlds are archives which have to be unpacked into `saves/`
+    World,
+    /// Download failed or the file doesn't match its hash
+    DownloadFailed(String),
+}
+
+/// File which has to be downloaded by the user from the CurseForge website
+#[derive(Debug, Clone)]
+pub struct ManualDownload {
+    pub project_id: u64,
+    pub file_id: u64,
+    pub project_name: Option<String>,
+    pub file_name: Option<String>,
+    /// Page of the file on the website
+    pub url: Option<String>,
+    /// Directory to put the downloaded file into
+    pub dest: PathBuf,
+    pub reason: ManualReason,
+}
+
+/// Imported instance and the files left for the user
+#[derive(Debug)]
+pub struct CurseForgeImport {
+    pub instance: Instance,
+    pub manual_downloads: Vec<ManualDownload>,
+}
+
+/// Parse mod loader id of the manifest, e.g. `forge-47.2.0`
+fn parse_loader(id: &str) -> Option<Loader> {
+    let (kind, version) = id.split_once('-')?;
+    let kind = match kind {
+        "forge" => LoaderKind::Forge,
+        "neoforge" => LoaderKind::NeoForge,
+        "fabric" => LoaderKind::Fabric,
+        "quilt" => LoaderKind::Quilt,
+        _ => return None,
+    };
+    Some(Loader {
+        kind,
+        version: version.to_string(),
+    })
+}
+
+/// Game directory of the project class, mods by default
+fn get_class_dir(class_id: Option<u64>) -> &'static str {
+    match class_id {
+        Some(12) => "resourcepacks",
+        Some(6552) => "shaderpacks",
+        Some(WORLD_CLASS_ID) => "saves",
+        _ => "mods",
+    }
+}
+
+pub fn read_manifest(path: &Path) -> Result<JsonCurseForgeManifest, Error> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+    let mut file = archive
+        .by_name(MANIFEST_FILE)
+        .map_err(|_| Error::Instance(format!("{path:?} is not a CurseForge modpack")))?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Download files of the pack, files which can't be downloaded are returned
+async fn download_files(
+    api: &CurseForgeApi,
+    manifest: &JsonCurseForgeManifest,
+    game_dir: &Path,
+) -> Result<Vec<ManualDownload>, Error> {
+    let entries: Vec<_> = manifest.files.iter().filter(|i| i.required).collect();
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    log::info!("Resolving {} files...", entries.len());
+    let file_ids: Vec<u64> = entries.iter().map(|i| i.file_id).collect();
+    let mod_ids: Vec<u64> = entries.iter().map(|i| i.project_id).collect();
+    let files: HashMap<u64, _> = api
+        .get_files(&file_ids)
+        .await?
+        .into_iter()
+        .map(|i| (i.id, i))
+        .collect();
+    let projects: HashMap<u64, JsonCurseForgeMod> = api
+        .get_mods(&mod_ids)
+        .await?
+        .into_iter()
+        .map(|i| (i.id, i))
+        .collect();
+
+    let client = reqwest::Client::builder().build()?;
+    let mut manual_downloads = Vec::new();
+    for entry in entries {
+        let project = projects.get(&entry.project_id);
+        let class_id = project.and_then(|i| i.class_id);
+        let dest = game_dir.join(get_class_dir(class_id));
+        let file = files.get(&entry.file_id);
+
+        let result = match file {
+            None => Err(ManualReason::FileNotFound),
+            Some(_) if class_id == Some(WORLD_CLASS_ID) => Err(ManualReason::World),
+            Some(file) => download_file(file, &dest, &client).await,
+        };
+        let Err(reason) = result else {
+            continue;
+        };
+
+        let manual = ManualDownload {
+            project_id: entry.project_id,
+            file_id: entry.file_id,
+            project_name: project.map(|i| i.name.clone()),
+            file_name: file.map(|i| i.file_name.clone()),
+            url: project
+                .and_then(|i| i.links.as_ref())
+                .and_then(|i| i.website_url.as_ref())
+                .map(|i| format!("{}/files/{}", i.trim_end_matches('/'), entry.file_id)),
+            dest,
+            reason,
+        };
+        log::warn!(
+            "{} ({}) has to be downloaded manually from {}: {:?}",
+            manual.project_name.as_deref().unwrap_or("Unknown project"),
+            manual.file_name.as_deref().unwrap_or("unknown file"),
+            manual.url.as_deref().unwrap_or("CurseForge"),
+            manual.reason,
+        );
+        manual_downloads.push(manual);
+    }
+    Ok(manual_downloads)
+}
+
+/// Download single file of the pack into `dest`
+///
+/// The file is verified with sha1, or md5 if sha1 is missing
+async fn download_file(
+    file: &JsonCurseForgeFile,
+    dest: &Path,
+    client: &reqwest::Client,
+) -> Result<(), ManualReason> {
+    let Some(url) = &file.download_url else {
+        return Err(ManualReason::DownloadDisallowed);
+    };
+    if file.file_name.contains(['/', '\\']) || file.file_name.starts_with('.') {
+        return Err(ManualReason::DownloadFailed(format!(
+            "Invalid file name {:?}",
+            file.file_name
+        )));
+    }
+
+    log::info!("Downloading {}...", file.file_name);
+    let path = dest.join(&file.file_name);
+    let hashes = FileHashes {
+        sha1: file.get_sha1(),
+        sha512: None,
+        md5: file.get_md5().filter(|_| file.get_sha1().is_none()),
+    };
+    let result = match hashes.sha1.is_some() || hashes.md5.is_some() {
+        true => mods::download_verified(url, &path, &hashes, client).await,
+        false => {
+            log::warn!("{} has no hashes, it is not verified", file.file_name);
+            async {
+                tokio::fs::create_dir_all(dest).await?;
+                install::download_file(url, &path, client).await
+            }
+            .await
+        }
+    };
+    result.map_err(|err| ManualReason::DownloadFailed(format!("{err:?}")))
+}
+
+/// Get overrides directory of the pack, an empty one would extract the whole archive
+fn get_overrides_dir(manifest: &JsonCurseForgeManifest) -> &str {
+    match manifest.overrides.trim_matches('/') {
+        "" | "." => DEFAULT_OVERRIDES_DIR,
+        dir => dir,
+    }
+}
+
+/// Import CurseForge modpack zip at `path` as a new instance
+///
+/// `name` overrides the pack name. Files of the pack are resolved with `api`,
+/// the ones with disallowed or failed downloads are returned as manual downloads
+/// instead of failing the import
+pub async fn import_curseforge(
+    launcher: &Launcher,
+    api: &CurseForgeApi,
+    path: &Path,
+    name: Option<&str>,
+) -> Result<CurseForgeImport, Error> {
+    let manifest = read_manifest(path)?;
+    if manifest.manifest_type != "minecraftModpack" {
+        return Err(Error::Instance(format!(
+            "Manifest type {} is not supported",
+            manifest.manifest_type
+        )));
+    }
+    let loader_id = manifest
+        .minecraft
+        .mod_loaders
+        .iter()
+        .find(|i| i.primary)
+        .or_else(|| manifest.minecraft.mod_loaders.first())
+        .map(|i| i.id.as_str());
+    let loader = match loader_id {
+        Some(id) => Some(
+            parse_loader(id).ok_or_else(|| Error::Instance(format!("Unknown mod loader {id}")))?,
+        ),
+        None => None,
+    };
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => sanitize_instance_name(&manifest.name),
+    };
+
+    log::info!("Importing modpack {name} from {path:?}...");
+    let mut instance = instances::create_instance(launcher, &name, &manifest.minecraft.version)?;
+    instance.loader = loader;
+
+    // Недоустановленный модпак удаляется целиком
+    let game_dir = instance.get_game_dir();
+    let result = async {
+        let manual_downloads = download_files(api, &manifest, &game_dir).await?;
+        extract_zip_dir(path, get_overrides_dir(&manifest), &game_dir)?;
+        instance.save()?;
+        let info = instances::install_instance(launcher, &instance).await?;
+        instance.get_java(launcher, &info).await?;
+        Ok(manual_downloads)
+    }
+    .await;
+    let manual_downloads = match result {
+        Ok(manual_downloads) => manual_downloads,
+        Err(err) => {
+            let _ = fs::remove_dir_all(instance.get_path());
+            return Err(err);
+        }
+    };
+
+    log::info!(
+        "Modpack {name} imported, {} files to download manually",
+        manual_downloads.len()
+    );
+    Ok(CurseForgeImport {
+        instance,
+        manual_downloads,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use serde_json::json;
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+    use crate::{
+        auth::Secret,
+        helpers::testing::{MockServer, TempLauncher},
+        utils,
+    };
+
+    fn get_api(server: &MockServer) -> CurseForgeApi {
+        CurseForgeApi::new(&server.url, Secret::new("key"))
+    }
+
+    fn get_manifest(files: serde_json::Value) -> JsonCurseForgeManifest {
+        serde_json::from_value(json!({
+            "minecraft": {
+                "version": "1.20.1",
+                "modLoaders": [{ "id": "forge-47.2.0", "primary": true }],
+            },
+            "manifestType": "minecraftModpack",
+            "name": "Pack",
+            "files": files,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn loaders_are_parsed() {
+        let cases = [
+            ("forge-47.2.0", LoaderKind::Forge, "47.2.0"),
+            (
+                "neoforge-20.4.80-beta",
+                LoaderKind::NeoForge,
+                "20.4.80-beta",
+            ),
+            ("fabric-0.15.7", LoaderKind::Fabric, "0.15.7"),
+            ("quilt-0.23.1", LoaderKind::Quilt, "0.23.1"),
+        ];
+        for (id, kind, version) in cases {
+            let loader = parse_loader(id).unwrap();
+            assert_eq!(loader.kind, kind);
+            assert_eq!(loader.version, version);
+        }
+        assert!(parse_loader("rift-1.0").is_none());
+        assert!(parse_loader("forge").is_none());
+    }
+
+    #[test]
+    fn manifest_is_read_from_zip() {
+        let temp = TempLauncher::new();
+        let path = temp.path().join("pack.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file(MANIFEST_FILE, SimpleFileOptions::default())
+            .unwrap();
+        let manifest = json!({
+            "minecraft": { "version": "1.20.1", "modLoaders": [{ "id": "fabric-0.15.7" }] },
+            "manifestType": "minecraftModpack",
+            "name": "Pack",
+            "files": [
+                { "projectID": 1, "fileID": 10 },
+                { "projectID": 2, "fileID": 20, "required": false },
+            ],
+        });
+        zip.write_all(manifest.to_string().as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let manifest = read_manifest(&path).unwrap();
+        assert_eq!(manifest.minecraft.version, "1.20.1");
+        assert_eq!(manifest.minecraft.mod_loaders[0].id, "fabric-0.15.7");
+        assert_eq!(manifest.overrides, "overrides");
+        assert!(manifest.files[0].required);
+        assert!(!manifest.files[1].required);
+        assert_eq!(manifest.files[1].file_id, 20);
+
+        let path = temp.path().join("empty.zip");
+        ZipWriter::new(fs::File::create(&path).unwrap())
+            .finish()
+            .unwrap();
+        assert!(matches!(read_manifest(&path), Err(Error::Instance(_))));
+    }
+
+    #[test]
+    fn worlds_go_to_saves() {
+        assert_eq!(get_class_dir(Some(6)), "mods");
+        assert_eq!(get_class_dir(Some(12)), "resourcepacks");
+        assert_eq!(get_class_dir(Some(6552)), "shaderpacks");
+        assert_eq!(get_class_dir(Some(WORLD_CLASS_ID)), "saves");
+        assert_eq!(get_class_dir(None), "mods");
+    }
+
+    #[tokio::test]
+    async fn undownloadable_files_are_reported() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let data = b"mod jar".to_vec();
+        server.route("/a.jar", 200, data.clone());
+        server.route("/b.jar", 200, data.clone());
+        server.route("/c.jar", 200, data.clone());
+        let link = |slug: &str| json!({ "websiteUrl": format!("https://www.curseforge.com/minecraft/mc-mods/{slug}") });
+        server.route_json(
+            "/v1/mods",
+            json!({ "data": [
+                { "id": 1, "name": "Md5 Mod", "slug": "a", "classId": 6, "links": link("a") },
+                { "id": 2, "name": "Unhashed Mod", "slug": "b", "classId": 6 },
+                { "id": 3, "name": "Private Mod", "slug": "c", "classId": 6, "links": link("c") },
+                { "id": 4, "name": "Broken Mod", "slug": "d", "classId": 6 },
+                { "id": 5, "name": "World", "slug": "e", "classId": 17 },
+            ]}),
+        );
+        server.route_json(
+            "/v1/mods/files",
+            json!({ "data": [
+                {
+                    "id": 10, "modId": 1, "fileName": "a.jar",
+                    "downloadUrl": format!("{}/a.jar", server.url),
+                    "hashes": [{ "value": utils::get_md5(&data), "algo": 2 }],
+                },
+                {
+                    "id": 20, "modId": 2, "fileName": "b.jar",
+                    "downloadUrl": format!("{}/b.jar", server.url),
+                },
+                { "id": 30, "modId": 3, "fileName": "c.jar", "downloadUrl": null },
+                {
+                    "id": 40, "modId": 4, "fileName": "d.jar",
+                    "downloadUrl": format!("{}/missing.jar", server.url),
+                    "hashes": [{ "value": "0", "algo": 1 }],
+                },
+                {
+                    "id": 50, "modId": 5, "fileName": "world.zip",
+                    "downloadUrl": format!("{}/c.jar", server.url),
+                },
+            ]}),
+        );
+        let manifest = get_manifest(json!([
+            { "projectID": 1, "fileID": 10 },
+            { "projectID": 2, "fileID": 20 },
+            { "projectID": 3, "fileID": 30 },
+            { "projectID": 4, "fileID": 40 },
+            { "projectID": 5, "fileID": 50 },
+            { "projectID": 6, "fileID": 60 },
+            { "projectID": 7, "fileID": 70, "required": false },
+        ]));
+
+        let game_dir = temp.path();
+        let manual = download_files(&get_api(&server), &manifest, &game_dir)
+            .await
+            .unwrap();
+        assert_eq!(fs::read(game_dir.join("mods").join("a.jar")).unwrap(), data);
+        assert_eq!(fs::read(game_dir.join("mods").join("b.jar")).unwrap(), data);
+        assert!(!game_dir.join("mods").join("c.jar").exists());
+        assert!(!game_dir.join("mods").join("d.jar").exists());
+        assert!(!game_dir.join("saves").exists());
+
+        let reasons: Vec<_> = manual.iter().map(|i| (i.file_id, &i.reason)).collect();
+        assert_eq!(reasons.len(), 4);
+        assert_eq!(reasons[0], (30, &ManualReason::DownloadDisallowed));
+        assert!(matches!(reasons[1], (40, ManualReason::DownloadFailed(_))));
+        assert_eq!(reasons[2], (50, &ManualReason::World));
+        assert_eq!(reasons[3], (60, &ManualReason::FileNotFound));
+        assert_eq!(
+            manual[0].url.as_deref(),
+            Some("https://www.curseforge.com/minecraft/mc-mods/c/files/30")
+        );
+        assert_eq!(manual[0].dest, game_dir.join("mods"));
+        assert_eq!(manual[2].dest, game_dir.join("saves"));
+
+        let request = server
+            .requests()
+            .into_iter()
+            .find(|i| i.target == "/v1/mods/files")
+            .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.headers.get("x-api-key").unwrap(), "key");
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body, json!({ "fileIds": [10, 20, 30, 40, 50, 60] }));
+    }
+
+    fn write_pack(path: &Path, overrides: &str) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        let options = SimpleFileOptions::default();
+        zip.start_file(MANIFEST_FILE, options).unwrap();
+        let manifest = json!({
+            "minecraft": { "version": "1.20.1", "modLoaders": [] },
+            "manifestType": "minecraftModpack",
+            "name": "Pack",
+            "files": [],
+            "overrides": overrides,
+        });
+        zip.write_all(manifest.to_string().as_bytes()).unwrap();
+        zip.start_file("overrides/config/a.txt", options).unwrap();
+        zip.write_all(b"config").unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn empty_overrides_dir_is_not_the_whole_archive() {
+        let temp = TempLauncher::new();
+        for overrides in ["", "/", "."] {
+            let path = temp.path().join("pack.zip");
+            write_pack(&path, overrides);
+            let manifest = read_manifest(&path).unwrap();
+            assert_eq!(get_overrides_dir(&manifest), "overrides");
+
+            let dest = temp.path().join("game");
+            extract_zip_dir(&path, get_overrides_dir(&manifest), &dest).unwrap();
+            assert_eq!(fs::read(dest.join("config/a.txt")).unwrap(), b"config");
+            assert!(!dest.join(MANIFEST_FILE).exists());
+            assert!(!dest.join("overrides").exists());
+            fs::remove_dir_all(dest).unwrap();
+        }
+
+        let mut manifest = get_manifest(json!([]));
+        manifest.overrides = "files/".to_string();
+        assert_eq!(get_overrides_dir(&manifest), "files");
+    }
+
+    #[tokio::test]
+    async fn failed_install_removes_instance() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let path = temp.path().join("pack.zip");
+        write_pack(&path, "overrides");
+        // Битый json версии, установка падает без обращения к сети
+        let version_dir = temp.path().join("versions/1.20.1");
+        fs::create_dir_all(&version_dir).unwrap();
+        fs::write(version_dir.join("1.20.1.json"), "{}").unwrap();
+
+        let result = import_curseforge(&temp.launcher, &get_api(&server), &path, None).await;
+        assert!(result.is_err());
+        assert!(
+            !instances::get_instances_dir(&temp.launcher)
+                .join("Pack")
+                .exists()
+        );
+        assert!(server.requests().is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+pub mod curseforge;
+pub mod mrpack;
+pub mod multimc;
+pub mod official;
+
+use std::{fs, io, path::Path};
+
+use zip::ZipArchive;
+
+use crate::types::{Error, LaunchOptions};
+
+/// Replace characters which can't be used in the instance name
+pub fn sanitize_instance_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .trim_start_matches('.')
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    name.trim().to_string()
+}
+
+/// Parse `-Xmx` / `-Xms` value into megabytes, e.g. `2G`, `2048M` or `2097152k`
+pub fn parse_memory(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, ""),
+    };
+    let number: u64 = number.parse().ok()?;
+    let megabytes = match unit.to_ascii_lowercase().as_str() {
+        "" => number / 1024 / 1024,
+        "k" => number / 1024,
+        "m" => number,
+        "g" => number * 1024,
+        "t" => number * 1024 * 1024,
+        _ => return None,
+    };
+    u32::try_from(megabytes).ok()
+}
+
+/// Split java arguments of other launchers, heap size is moved into `options` memory
+pub fn parse_java_args(args: &str, options: &mut LaunchOptions) {
+    for arg in args.split_whitespace() {
+        if let Some(memory) = arg.strip_prefix("-Xmx").and_then(parse_memory) {
+            options.max_memory = Some(memory);
+        } else if let Some(memory) = arg.strip_prefix("-Xms").and_then(parse_memory) {
+            options.min_memory = Some(memory);
+        } else {
+            options.jvm_args.push(arg.to_string());
+        }
+    }
+}
+
+/// Extract files of the `prefix` directory of the zip archive into `dest`
+pub fn extract_zip_dir(path: &Path, prefix: &str, dest: &Path) -> Result<(), Error> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let Some(relative) = file
+            .enclosed_name()
+            .and_then(|i| i.strip_prefix(prefix).ok().map(Path::to_path_buf))
+        else {
+            continue;
+        };
+
+        let outpath = dest.join(relative);
+        if file.is_dir() {
+            fs::create_dir_all(&outpath)?;
+            continue;
+        }
+        if let Some(parent) = outpath.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut fs::File::create(&outpath)?)?;
+    }
+    Ok(())
+}
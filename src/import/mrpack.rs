@@ -0,0 +1,486 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use reqwest::Url;
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use super::{extract_zip_dir, sanitize_instance_name};
+use crate::{
+    instances::{
+        self, Instance, Loader, LoaderKind, SAVES_DIR,
+        archive::{add_dir, is_excluded_entry},
+    },
+    internal_types::shared_modrinth::{_JsonModrinthHashes, _JsonMrpackFile, JsonMrpackIndex},
+    mods::{self, FileHashes, InstalledMod},
+    types::{Error, Launcher},
+    utils,
+};
+
+const INDEX_FILE: &str = "modrinth.index.json";
+const OVERRIDES_DIR: &str = "overrides";
+const CLIENT_OVERRIDES_DIR: &str = "client-overrides";
+const MODRINTH_CDN_URL: &str = "https://cdn.modrinth.com";
+
+fn get_loader_kind(key: &str) -> Option<LoaderKind> {
+    match key {
+        "fabric-loader" => Some(LoaderKind::Fabric),
+        "quilt-loader" => Some(LoaderKind::Quilt),
+        "forge" => Some(LoaderKind::Forge),
+        "neoforge" => Some(LoaderKind::NeoForge),
+        _ => None,
+    }
+}
+
+fn get_loader_key(kind: LoaderKind) -> &'static str {
+    match kind {
+        LoaderKind::Fabric => "fabric-loader",
+        LoaderKind::Quilt => "quilt-loader",
+        LoaderKind::Forge => "forge",
+        LoaderKind::NeoForge => "neoforge",
+    }
+}
+
+/// Check that the pack path stays inside the game directory
+fn is_safe_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|i| matches!(i, Component::Normal(_)))
+}
+
+/// Build [`InstalledMod`] from Modrinth CDN url `data/<project>/versions/<version>/<file>`
+fn get_installed_mod(file: &_JsonMrpackFile) -> Option<InstalledMod> {
+    let file_name = file.path.strip_prefix("mods/")?;
+    let url = file
+        .downloads
+        .iter()
+        .find(|i| i.starts_with(MODRINTH_CDN_URL))?;
+    let url = Url::parse(url).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.collect();
+    let ["data", project_id, "versions", version_id, _] = segments.as_slice() else {
+        return None;
+    };
+
+    Some(InstalledMod {
+        project_id: project_id.to_string(),
+        version_id: version_id.to_string(),
+        name: file_name.trim_end_matches(".jar").to_string(),
+        version_number: String::new(),
+        file_name: file_name.to_string(),
+        sha1: file.hashes.sha1.clone(),
+        dependency: false,
+    })
+}
+
+pub fn read_index(path: &Path) -> Result<JsonMrpackIndex, Error> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+    let mut file = archive
+        .by_name(INDEX_FILE)
+        .map_err(|_| Error::Instance(format!("{path:?} is not a Modrinth modpack")))?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Download client files of the pack, every url is tried until the hashes match
+async fn download_files(index: &JsonMrpackIndex, instance: &mut Instance) -> Result<(), Error> {
+    let game_dir = instance.get_game_dir();
+    let client = reqwest::Client::builder().build()?;
+
+    for file in &index.files {
+        if !is_safe_path(&file.path) {
+            return Err(Error::Instance(format!(
+                "Invalid file path {:?}",
+                file.path
+            )));
+        }
+        if file.env.as_ref().is_some_and(|i| i.client == "unsupported") {
+            continue;
+        }
+
+        log::info!("Downloading {}...", file.path);
+        let path = game_dir.join(&file.path);
+        let mut result = Err(Error::Instance(format!("No downloads for {}", file.path)));
+        for url in &file.downloads {
+            result = mods::download_verified(
+                url,
+                &path,
+                &FileHashes {
+                    sha1: Some(&file.hashes.sha1),
+                    sha512: Some(&file.hashes.sha512),
+                    md5: None,
+                },
+                &client,
+            )
+            .await;
+            match &result {
+                Ok(_) => break,
+                Err(err) => log::warn!("Failed to download {url}: {err:?}"),
+            }
+        }
+        result?;
+
+        if let Some(installed_mod) = get_installed_mod(file) {
+            instance.mods.push(installed_mod);
+        }
+    }
+    Ok(())
+}
+
+/// Extract `overrides` and then `client-overrides` of the pack, so client files win
+fn apply_overrides(path: &Path, game_dir: &Path) -> Result<(), Error> {
+    extract_zip_dir(path, OVERRIDES_DIR, game_dir)?;
+    extract_zip_dir(path, CLIENT_OVERRIDES_DIR, game_dir)
+}
+
+/// Import `.mrpack` modpack at `path` as a new instance
+///
+/// `name` overrides the pack name. Client files are downloaded and verified,
+/// `overrides` and then `client-overrides` are applied over them
+pub async fn import_mrpack(
+    launcher: &Launcher,
+    path: &Path,
+    name: Option<&str>,
+) -> Result<Instance, Error> {
+    let index = read_index(path)?;
+    if index.game != "minecraft" {
+        return Err(Error::Instance(format!(
+            "Modpack for {} is not supported",
+            index.game
+        )));
+    }
+    let version = index
+        .dependencies
+        .get("minecraft")
+        .ok_or_else(|| Error::Instance(format!("Minecraft version of {path:?} not found")))?;
+    let loader = index.dependencies.iter().find_map(|(key, version)| {
+        get_loader_kind(key).map(|kind| Loader {
+            kind,
+            version: version.clone(),
+        })
+    });
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => sanitize_instance_name(&index.name),
+    };
+
+    log::info!("Importing modpack {name} from {path:?}...");
+    let mut instance = instances::create_instance(launcher, &name, version)?;
+    instance.loader = loader;
+
+    // Недоустановленный модпак удаляется целиком
+    let result = async {
+        download_files(&index, &mut instance).await?;
+        apply_overrides(path, &instance.get_game_dir())?;
+        instance.save()?;
+        let info = instances::install_instance(launcher, &instance).await?;
+        instance.get_java(launcher, &info).await
+    }
+    .await;
+    if let Err(err) = result {
+        let _ = fs::remove_dir_all(instance.get_path());
+        return Err(err);
+    }
+
+    log::info!("Modpack {name} imported!");
+    Ok(instance)
+}
+
+/// Export instance `name` as `.mrpack` modpack with `version_id` as the pack version
+///
+/// Mods installed from Modrinth are referenced by their urls, the rest of the
+/// game directory except saves and logs is put into `overrides`
+pub async fn export_mrpack(
+    launcher: &Launcher,
+    name: &str,
+    dest: &Path,
+    version_id: &str,
+) -> Result<(), Error> {
+    let instance = instances::load_instance(launcher, name)?;
+    let mods_dir = mods::get_mods_dir(&instance);
+
+    let mut files = Vec::new();
+    let mut indexed = HashSet::new();
+    for installed_mod in &instance.mods {
+        let path = mods_dir.join(&installed_mod.file_name);
+        let Ok(data) = tokio::fs::read(&path).await else {
+            log::warn!("Mod {} not found, skipping", installed_mod.file_name);
+            continue;
+        };
+
+        let mut url = Url::parse(MODRINTH_CDN_URL)
+            .map_err(|err| Error::Instance(format!("Invalid url: {err}")))?;
+        url.path_segments_mut()
+            .map_err(|_| Error::Instance("Invalid url".to_string()))?
+            .extend([
+                "data",
+                &installed_mod.project_id,
+                "versions",
+                &installed_mod.version_id,
+                &installed_mod.file_name,
+            ]);
+        files.push(_JsonMrpackFile {
+            path: format!("mods/{}", installed_mod.file_name),
+            hashes: _JsonModrinthHashes {
+                sha1: utils::get_sha1(&data),
+                sha512: utils::get_sha512(&data),
+            },
+            env: None,
+            downloads: vec![url.to_string()],
+            file_size: data.len() as u64,
+        });
+        indexed.insert(PathBuf::from("mods").join(&installed_mod.file_name));
+    }
+
+    let mut dependencies = BTreeMap::from([("minecraft".to_string(), instance.version.clone())]);
+    if let Some(loader) = &instance.loader {
+        dependencies.insert(
+            get_loader_key(loader.kind).to_string(),
+            loader.version.clone(),
+        );
+    }
+    let index = JsonMrpackIndex {
+        format_version: 1,
+        game: "minecraft".to_string(),
+        version_id: version_id.to_string(),
+        name: instance.name.clone(),
+        summary: None,
+        files,
+        dependencies,
+    };
+
+    log::info!("Exporting modpack {name} to {dest:?}...");
+    let mut zip = ZipWriter::new(fs::File::create(dest)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(INDEX_FILE, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&index)?)?;
+
+    let game_dir = instance.get_game_dir();
+    if game_dir.exists() {
+        add_dir(&mut zip, &game_dir, &game_dir, OVERRIDES_DIR, &|relative| {
+            let Some(Component::Normal(first)) = relative.components().next() else {
+                return false;
+            };
+            !is_excluded_entry(first) && first != SAVES_DIR && !indexed.contains(relative)
+        })?;
+    }
+
+    zip.finish()?;
+    log::info!("Modpack {name} exported!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::helpers::testing::{MockServer, TempLauncher};
+
+    fn write_pack(path: &Path, index: &Value, files: &[(&str, &str)]) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        let options = SimpleFileOptions::default();
+        zip.start_file(INDEX_FILE, options).unwrap();
+        zip.write_all(&serde_json::to_vec(index).unwrap()).unwrap();
+        for (name, data) in files {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn get_index(files: Value) -> Value {
+        json!({
+            "formatVersion": 1,
+            "game": "minecraft",
+            "versionId": "1.0.0",
+            "name": "Pack",
+            "files": files,
+            "dependencies": { "minecraft": "1.21.8", "fabric-loader": "0.16.14" },
+        })
+    }
+
+    fn get_file(path: &str, data: &str, downloads: &[String]) -> Value {
+        json!({
+            "path": path,
+            "hashes": {
+                "sha1": utils::get_sha1(data.as_bytes()),
+                "sha512": utils::get_sha512(data.as_bytes()),
+            },
+            "downloads": downloads,
+            "fileSize": data.len(),
+        })
+    }
+
+    #[test]
+    fn unsafe_paths_are_rejected() {
+        assert!(is_safe_path("mods/a.jar"));
+        assert!(is_safe_path("config/a/b.toml"));
+        assert!(!is_safe_path(""));
+        assert!(!is_safe_path("../a.jar"));
+        assert!(!is_safe_path("mods/../../a.jar"));
+        assert!(!is_safe_path("./mods/a.jar"));
+        assert!(!is_safe_path("/etc/passwd"));
+    }
+
+    #[test]
+    fn installed_mod_is_parsed_from_cdn_url() {
+        let url = format!("{MODRINTH_CDN_URL}/data/AANobbMI/versions/mc1Ve8Ov/sodium-0.6.jar");
+        let file: _JsonMrpackFile = serde_json::from_value(get_file(
+            "mods/sodium-0.6.jar",
+            "",
+            &["https://example.com/sodium.jar".to_string(), url],
+        ))
+        .unwrap();
+        let installed_mod = get_installed_mod(&file).unwrap();
+        assert_eq!(installed_mod.project_id, "AANobbMI");
+        assert_eq!(installed_mod.version_id, "mc1Ve8Ov");
+        assert_eq!(installed_mod.file_name, "sodium-0.6.jar");
+        assert_eq!(installed_mod.name, "sodium-0.6");
+        assert_eq!(installed_mod.sha1, file.hashes.sha1);
+
+        let mut other = file.clone();
+        other.downloads = vec!["https://example.com/sodium.jar".to_string()];
+        assert!(get_installed_mod(&other).is_none());
+        let mut other = file.clone();
+        other.path = "resourcepacks/sodium-0.6.jar".to_string();
+        assert!(get_installed_mod(&other).is_none());
+        let mut other = file;
+        other.downloads = vec![format!("{MODRINTH_CDN_URL}/data/AANobbMI/sodium.jar")];
+        assert!(get_installed_mod(&other).is_none());
+    }
+
+    #[tokio::test]
+    async fn client_files_and_overrides_are_applied() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        server.route("/a.jar", 200, "mod");
+        server.route("/server.jar", 200, "server");
+        let mut server_file = get_file(
+            "mods/server.jar",
+            "server",
+            &[format!("{}/server.jar", server.url)],
+        );
+        server_file["env"] = json!({ "client": "unsupported", "server": "required" });
+        let index = get_index(json!([
+            get_file("mods/a.jar", "mod", &[format!("{}/a.jar", server.url)]),
+            server_file,
+        ]));
+        let path = temp.path().join("pack.mrpack");
+        write_pack(
+            &path,
+            &index,
+            &[
+                ("overrides/config/a.txt", "common"),
+                ("overrides/config/b.txt", "common"),
+                ("client-overrides/config/a.txt", "client"),
+            ],
+        );
+
+        let index = read_index(&path).unwrap();
+        let mut instance = instances::create_instance(&temp.launcher, "test", "1.21.8").unwrap();
+        download_files(&index, &mut instance).await.unwrap();
+        let game_dir = instance.get_game_dir();
+        apply_overrides(&path, &game_dir).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(game_dir.join("mods/a.jar")).unwrap(),
+            "mod"
+        );
+        assert!(!game_dir.join("mods/server.jar").exists());
+        assert!(!server.was_requested("/server.jar"));
+        assert_eq!(
+            fs::read_to_string(game_dir.join("config/a.txt")).unwrap(),
+            "client"
+        );
+        assert_eq!(
+            fs::read_to_string(game_dir.join("config/b.txt")).unwrap(),
+            "common"
+        );
+        // Мод без ссылки на Modrinth не записывается в установленные
+        assert!(instance.mods.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_install_removes_instance() {
+        let temp = TempLauncher::new();
+        let path = temp.path().join("pack.mrpack");
+        let mut index = get_index(json!([]));
+        // Без загрузчика, чтобы не обращаться к его API
+        index["dependencies"] = json!({ "minecraft": "1.21.8" });
+        write_pack(&path, &index, &[("overrides/options.txt", "")]);
+        // Битый json версии, установка падает без обращения к сети
+        let version_dir = temp.path().join("versions/1.21.8");
+        fs::create_dir_all(&version_dir).unwrap();
+        fs::write(version_dir.join("1.21.8.json"), "{}").unwrap();
+
+        assert!(import_mrpack(&temp.launcher, &path, None).await.is_err());
+        assert!(
+            !instances::get_instances_dir(&temp.launcher)
+                .join("Pack")
+                .exists()
+        );
+    }
+
+    #[tokio::test]
+    async fn exported_pack_is_imported_back() {
+        let temp = TempLauncher::new();
+        let mut instance = instances::create_instance(&temp.launcher, "test", "1.21.8").unwrap();
+        instance.loader = Some(Loader {
+            kind: LoaderKind::Fabric,
+            version: "0.16.14".to_string(),
+        });
+        instance.mods.push(InstalledMod {
+            project_id: "AANobbMI".to_string(),
+            version_id: "mc1Ve8Ov".to_string(),
+            name: "Sodium".to_string(),
+            version_number: "0.6".to_string(),
+            file_name: "sodium.jar".to_string(),
+            sha1: utils::get_sha1(b"mod"),
+            dependency: false,
+        });
+        instance.save().unwrap();
+        let game_dir = instance.get_game_dir();
+        for (name, data) in [
+            ("mods/sodium.jar", "mod"),
+            ("config/sodium.json", "{}"),
+            ("saves/world/level.dat", ""),
+            ("versions/1.21.8/1.21.8.json", "{}"),
+        ] {
+            let path = game_dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, data).unwrap();
+        }
+
+        let path = temp.path().join("pack.mrpack");
+        export_mrpack(&temp.launcher, "test", &path, "2.0.0")
+            .await
+            .unwrap();
+
+        let index = read_index(&path).unwrap();
+        assert_eq!(index.version_id, "2.0.0");
+        assert_eq!(index.dependencies["minecraft"], "1.21.8");
+        assert_eq!(index.dependencies["fabric-loader"], "0.16.14");
+        assert_eq!(index.files.len(), 1);
+        assert_eq!(index.files[0].hashes.sha512, utils::get_sha512(b"mod"));
+        let installed_mod = get_installed_mod(&index.files[0]).unwrap();
+        assert_eq!(installed_mod.project_id, "AANobbMI");
+        assert_eq!(installed_mod.version_id, "mc1Ve8Ov");
+        assert_eq!(installed_mod.file_name, "sodium.jar");
+
+        // Моды из индекса, сохранения и общие файлы не попадают в overrides
+        let dest = temp.path().join("imported");
+        apply_overrides(&path, &dest).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("config/sodium.json")).unwrap(),
+            "{}"
+        );
+        assert!(!dest.join("mods/sodium.jar").exists());
+        assert!(!dest.join("saves").exists());
+        assert!(!dest.join("versions").exists());
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use super::{parse_java_args, sanitize_instance_name};
+use crate::{
+    instances::{self, Instance, JavaChoice, Loader, LoaderKind},
+    internal_types::shared_multimc::JsonMultiMcPack,
+    types::{Error, Launcher},
+    utils,
+};
+
+const CONFIG_FILE: &str = "instance.cfg";
+const PACK_FILE: &str = "mmc-pack.json";
+
+/// Parse Qt INI file, keys of all sections are put together
+fn parse_config(data: &str) -> HashMap<String, String> {
+    let mut config = HashMap::new();
+    for line in data.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(['#', ';', '[']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let value = value.trim();
+        // Qt берет в кавычки значения со спецсимволами
+        let value = match value.strip_prefix('"').and_then(|i| i.strip_suffix('"')) {
+            Some(value) => value.replace("\\\"", "\"").replace("\\\\", "\\"),
+            None => value.to_string(),
+        };
+        config.insert(key.trim().to_string(), value);
+    }
+    config
+}
+
+/// Check if the setting group is overridden by the instance
+///
+/// Without the `Override*` key the instance uses global launcher settings,
+/// its own values are stale then
+fn is_overridden(config: &HashMap<String, String>, key: &str) -> bool {
+    config.get(key).is_some_and(|value| value == "true")
+}
+
+fn get_loader_kind(uid: &str) -> Option<LoaderKind> {
+    match uid {
+        "net.fabricmc.fabric-loader" => Some(LoaderKind::Fabric),
+        "org.quiltmc.quilt-loader" => Some(LoaderKind::Quilt),
+        "net.minecraftforge" => Some(LoaderKind::Forge),
+        "net.neoforged" => Some(LoaderKind::NeoForge),
+        _ => None,
+    }
+}
+
+/// Apply java and window settings overridden by the MultiMC instance
+fn apply_settings(config: &HashMap<String, String>, instance: &mut Instance) {
+    if is_overridden(config, "OverrideJavaLocation")
+        && let Some(java_path) = config.get("JavaPath").filter(|i| !i.is_empty())
+    {
+        instance.java = JavaChoice::Path {
+            path: PathBuf::from(java_path),
+        };
+    }
+    if is_overridden(config, "OverrideJavaArgs")
+        && let Some(jvm_args) = config.get("JvmArgs")
+    {
+        parse_java_args(jvm_args, &mut instance.options);
+    }
+    if is_overridden(config, "OverrideMemory") {
+        let get_memory = |key: &str| config.get(key).and_then(|i| i.parse().ok());
+        instance.options.min_memory = get_memory("MinMemAlloc").or(instance.options.min_memory);
+        instance.options.max_memory = get_memory("MaxMemAlloc").or(instance.options.max_memory);
+    }
+    if is_overridden(config, "OverrideWindow") {
+        let get_size = |key: &str| config.get(key).and_then(|i| i.parse().ok());
+        instance.options.width = get_size("MinecraftWinWidth");
+        instance.options.height = get_size("MinecraftWinHeight");
+    }
+}
+
+/// Get game directory of the MultiMC instance, `.minecraft` or `minecraft`
+fn get_game_dir(path: &Path) -> Option<PathBuf> {
+    [".minecraft", "minecraft"]
+        .into_iter()
+        .map(|name| path.join(name))
+        .find(|dir| dir.is_dir())
+}
+
+/// Import MultiMC / Prism Launcher instance folder at `path`
+///
+/// `name` overrides the instance name from `instance.cfg`. Game directory is copied,
+/// version, loader and runtime are installed afterwards
+pub async fn import_instance(
+    launcher: &Launcher,
+    path: &Path,
+    name: Option<&str>,
+) -> Result<Instance, Error> {
+    let config = match std::fs::read_to_string(path.join(CONFIG_FILE)) {
+        Ok(data) => parse_config(&data),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(Error::Instance(format!(
+                "{path:?} is not a MultiMC instance"
+            )));
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut version = config.get("IntendedVersion").cloned();
+    let mut loader = None;
+    match std::fs::read(path.join(PACK_FILE)) {
+        Ok(data) => {
+            let pack: JsonMultiMcPack = serde_json::from_slice(&data)?;
+            for component in &pack.components {
+                let Some(component_version) = component.get_version() else {
+                    continue;
+                };
+                if component.uid == "net.minecraft" {
+                    version = Some(component_version.to_string());
+                } else if let Some(kind) = get_loader_kind(&component.uid) {
+                    loader = Some(Loader {
+                        kind,
+                        version: component_version.to_string(),
+                    });
+                }
+            }
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    let version = version
+        .ok_or_else(|| Error::Instance(format!("Minecraft version of {path:?} not found")))?;
+
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => {
+            let default = path
+                .file_name()
+                .map(|i| i.to_string_lossy().to_string())
+                .unwrap_or_default();
+            sanitize_instance_name(config.get("name").unwrap_or(&default))
+        }
+    };
+
+    log::info!("Importing MultiMC instance {name} from {path:?}...");
+    let mut instance = instances::create_instance(launcher, &name, &version)?;
+    instance.loader = loader;
+
+    apply_settings(&config, &mut instance);
+
+    // Недоустановленный инстанс удаляется целиком
+    let result = async {
+        instance.save()?;
+        if let Some(game_dir) = get_game_dir(path) {
+            utils::copy_dir(&game_dir, &instance.get_game_dir(), &|_| true)?;
+        }
+        let info = instances::install_instance(launcher, &instance).await?;
+        instance.get_java(launcher, &info).await
+    }
+    .await;
+    if let Err(err) = result {
+        let _ = std::fs::remove_dir_all(instance.get_path());
+        return Err(err);
+    }
+
+    log::info!("MultiMC instance {name} imported!");
+    Ok(instance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::TempLauncher;
+
+    const CONFIG: &str = r#"[General]
+InstanceType=OneSix
+name=My "Pack"
+# comment
+; comment
+JavaPath="C:\\Program Files\\Java\\bin\\javaw.exe"
+JvmArgs="-Xmx4G -XX:+UseG1GC -Dkey=\"a=b\""
+OverrideJavaArgs=true
+OverrideMemory=false
+MaxMemAlloc=8192
+MinecraftWinWidth=1280
+MinecraftWinHeight=720
+notes=
+"#;
+
+    #[test]
+    fn qt_config_is_parsed() {
+        let config = parse_config(CONFIG);
+        assert_eq!(config["name"], "My \"Pack\"");
+        assert_eq!(
+            config["JavaPath"],
+            "C:\\Program Files\\Java\\bin\\javaw.exe"
+        );
+        assert_eq!(config["JvmArgs"], "-Xmx4G -XX:+UseG1GC -Dkey=\"a=b\"");
+        assert_eq!(config["notes"], "");
+        assert!(!config.contains_key("[General]"));
+        assert!(!config.keys().any(|i| i.starts_with(['#', ';'])));
+    }
+
+    #[test]
+    fn only_overridden_settings_are_applied() {
+        let config = parse_config(CONFIG);
+        assert!(is_overridden(&config, "OverrideJavaArgs"));
+        assert!(!is_overridden(&config, "OverrideMemory"));
+        // Без ключа используются глобальные настройки
+        assert!(!is_overridden(&config, "OverrideJavaLocation"));
+        assert!(!is_overridden(&config, "OverrideWindow"));
+
+        let temp = TempLauncher::new();
+        let mut instance = instances::create_instance(&temp.launcher, "test", "1.21.8").unwrap();
+        apply_settings(&config, &mut instance);
+        assert!(matches!(instance.java, JavaChoice::Auto));
+        assert_eq!(instance.options.max_memory, Some(4096));
+        assert_eq!(instance.options.jvm_args, ["-XX:+UseG1GC", "-Dkey=\"a=b\""]);
+        assert_eq!(instance.options.width, None);
+        assert_eq!(instance.options.height, None);
+    }
+
+    #[tokio::test]
+    async fn failed_install_removes_instance() {
+        let temp = TempLauncher::new();
+        let source = temp.path().join("source");
+        std::fs::create_dir_all(source.join(".minecraft")).unwrap();
+        std::fs::write(source.join(".minecraft/options.txt"), "").unwrap();
+        std::fs::write(
+            source.join(CONFIG_FILE),
+            "name=Pack\nIntendedVersion=1.21.8\n",
+        )
+        .unwrap();
+        // Битый json версии, установка падает без обращения к сети
+        let version_dir = temp.path().join("versions/1.21.8");
+        std::fs::create_dir_all(&version_dir).unwrap();
+        std::fs::write(version_dir.join("1.21.8.json"), "{}").unwrap();
+
+        assert!(
+            import_instance(&temp.launcher, &source, None)
+                .await
+                .is_err()
+        );
+        assert!(
+            !instances::get_instances_dir(&temp.launcher)
+                .join("Pack")
+                .exists()
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::path::{self, Path, PathBuf};
+
+use uuid::Uuid;
+
+use super::{parse_java_args, sanitize_instance_name};
+use crate::{
+    instances::{self, Instance, JavaChoice},
+    internal_types::shared_profiles::{
+        _JsonLauncherProfile, _JsonProfileResolution, JsonLauncherProfiles,
+    },
+    types::{Error, Launcher, VersionsList},
+    utils,
+};
+
+const PROFILES_FILE: &str = "launcher_profiles.json";
+
+pub fn get_profiles_path(minecraft_dir: &Path) -> PathBuf {
+    minecraft_dir.join(PROFILES_FILE)
+}
+
+/// Read `launcher_profiles.json`, empty profiles are returned if it doesn't exist
+pub fn read_profiles(minecraft_dir: &Path) -> Result<JsonLauncherProfiles, Error> {
+    match std::fs::read(get_profiles_path(minecraft_dir)) {
+        Ok(data) => Ok(serde_json::from_slice(&data)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Ok(JsonLauncherProfiles::default())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn get_profile_name(id: &str, profile: &_JsonLauncherProfile) -> String {
+    let name = match (profile.name.as_str(), profile.profile_type.as_deref()) {
+        ("", Some("latest-release")) => "Latest release",
+        ("", Some("latest-snapshot")) => "Latest snapshot",
+        ("", _) => id,
+        (name, _) => name,
+    };
+    sanitize_instance_name(name)
+}
+
+/// Get version id of the profile, `latest-*` types are resolved with the versions list
+async fn get_profile_version(
+    profile: &_JsonLauncherProfile,
+    versions: &mut Option<VersionsList>,
+) -> Result<String, Error> {
+    let version_type = match profile.profile_type.as_deref() {
+        Some("latest-release") => "release",
+        Some("latest-snapshot") => "snapshot",
+        _ => match &profile.last_version_id {
+            Some(id) if !id.starts_with("latest-") => return Ok(id.clone()),
+            Some(id) if id == "latest-snapshot" => "snapshot",
+            _ => "release",
+        },
+    };
+
+    if versions.is_none() {
+        *versions = Some(utils::get_versions_list().await?);
+    }
+    // Список версий отсортирован от новых к старым
+    versions
+        .iter()
+        .flat_map(|i| i.versions.iter())
+        .find(|i| i.version_type == version_type)
+        .map(|i| i.id.clone())
+        .ok_or_else(|| Error::Instance(format!("Latest {version_type} not found")))
+}
+
+/// Copy version json and client of the official launcher into the shared store
+fn copy_version(launcher: &Launcher, minecraft_dir: &Path, id: &str) -> Result<(), Error> {
+    let src = minecraft_dir.join("versions").join(id);
+    let dest = launcher.path.join("versions").join(id);
+    if !src.join(format!("{id}.json")).exists() || dest.join(format!("{id}.json")).exists() {
+        return Ok(());
+    }
+
+    log::info!("Copying version {id} from {minecraft_dir:?}...");
+    utils::copy_dir(&src, &dest, &|entry| {
+        entry.file_name().is_some_and(|i| i != "natives")
+    })
+}
+
+/// Create instances from the profiles of the official launcher
+///
+/// Instances use `minecraft_dir` (or custom `gameDir` of the profile) as their game directory,
+/// so saves and mods are shared between the launchers. Profiles with names of the existing
+/// instances are skipped
+pub async fn import_profiles(
+    launcher: &Launcher,
+    minecraft_dir: &Path,
+) -> Result<Vec<Instance>, Error> {
+    let profiles = read_profiles(minecraft_dir)?;
+    let minecraft_dir = path::absolute(minecraft_dir)?;
+    let mut versions = None;
+    let mut imported = Vec::new();
+
+    for (id, profile) in &profiles.profiles {
+        let name = get_profile_name(id, profile);
+        if instances::validate_instance_name(&name).is_err() {
+            log::warn!("Skipping profile {id} with invalid name {name:?}");
+            continue;
+        }
+        if instances::get_instances_dir(launcher).join(&name).exists() {
+            log::info!("Instance {name} already exists, skipping profile {id}");
+            continue;
+        }
+
+        let version = get_profile_version(profile, &mut versions).await?;
+        copy_version(launcher, &minecraft_dir, &version)?;
+
+        let mut instance = instances::create_instance(launcher, &name, &version)?;
+        instance.profile_id = Some(id.clone());
+        instance.game_dir = Some(match &profile.game_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => minecraft_dir.clone(),
+        });
+        if let Some(java_dir) = &profile.java_dir {
+            instance.java = JavaChoice::Path {
+                path: PathBuf::from(java_dir),
+            };
+        }
+        if let Some(java_args) = &profile.java_args {
+            parse_java_args(java_args, &mut instance.options);
+        }
+        if let Some(resolution) = &profile.resolution {
+            instance.options.width = Some(resolution.width);
+            instance.options.height = Some(resolution.height);
+        }
+        instance.save()?;
+
+        imported.push(instance);
+    }
+
+    log::info!("Imported {} profiles", imported.len());
+    Ok(imported)
+}
+
+fn get_java_args(instance: &Instance) -> Option<String> {
+    let options = &instance.options;
+    let mut args = Vec::new();
+    if let Some(memory) = options.min_memory {
+        args.push(format!("-Xms{memory}M"));
+    }
+    if let Some(memory) = options.max_memory {
+        args.push(format!("-Xmx{memory}M"));
+    }
+    args.extend(options.jvm_args.iter().cloned());
+    (!args.is_empty()).then(|| args.join(" "))
+}
+
+/// Write `instances` into `launcher_profiles.json` of the official launcher
+///
+/// Profiles are matched by the key stored at import, instances without one get
+/// a new profile and remember its key. Other profiles and fields are kept as is
+pub fn write_profiles(minecraft_dir: &Path, instances: &mut [Instance]) -> Result<(), Error> {
+    let mut profiles = read_profiles(minecraft_dir)?;
+    let minecraft_dir_abs = path::absolute(minecraft_dir)?;
+
+    for instance in instances.iter_mut() {
+        let id = instance
+            .profile_id
+            .clone()
+            .filter(|id| profiles.profiles.contains_key(id))
+            .unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+        instance.profile_id = Some(id.clone());
+        let profile = profiles.profiles.entry(id).or_default();
+
+        let game_dir = path::absolute(instance.get_game_dir())?;
+        if profile.profile_type.is_none() {
+            profile.profile_type = Some("custom".to_string());
+        }
+        // `latest-*` профили всегда запускают последнюю версию, а без имени
+        // официальный лаунчер показывает их локализованное название
+        let is_latest = profile
+            .profile_type
+            .as_deref()
+            .is_some_and(|i| i.starts_with("latest-"));
+        if !is_latest || !profile.name.is_empty() {
+            profile.name = instance.name.clone();
+        }
+        if !is_latest {
+            profile.last_version_id = Some(instance.version.clone());
+        }
+        profile.game_dir =
+            (game_dir != minecraft_dir_abs).then(|| game_dir.to_string_lossy().to_string());
+        profile.java_args = get_java_args(instance);
+        profile.java_dir = match &instance.java {
+            JavaChoice::Path { path } => Some(path::absolute(path)?.to_string_lossy().to_string()),
+            _ => None,
+        };
+        profile.resolution = match (instance.options.width, instance.options.height) {
+            (Some(width), Some(height)) => Some(_JsonProfileResolution { width, height }),
+            _ => None,
+        };
+    }
+
+    log::info!(
+        "Writing {} profiles to {minecraft_dir:?}...",
+        instances.len()
+    );
+    std::fs::create_dir_all(minecraft_dir)?;
+    let path = get_profiles_path(minecraft_dir);
+    let tmp_path = minecraft_dir.join(format!("{PROFILES_FILE}.tmp"));
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(&profiles)?)?;
+    std::fs::rename(tmp_path, path)?;
+
+    for instance in instances.iter() {
+        instance.save()?;
+    }
+    Ok(())
+}
+
+/// Import profiles of the official launcher from its default directory
+pub async fn import_default_profiles(launcher: &Launcher) -> Result<Vec<Instance>, Error> {
+    import_profiles(launcher, &utils::get_minecraft_dir()).await
+}
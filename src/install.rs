@@ -1,4 +1,11 @@
-use std::path::Path;
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+};
+
+use futures::future::BoxFuture;
+use serde_json::{Map, Value};
 
 use crate::{
     helpers,
@@ -34,10 +41,79 @@ pub async fn download_file(url: &str, path: &Path, client: &reqwest::Client) ->
     Ok(())
 }
 
+/// How existing files are checked before they are downloaded again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCheck {
+    /// Existing file is trusted, used on every launch
+    Exists,
+    /// Existing file is hashed again, used on install and repair
+    Hash,
+}
+
+/// Check that the file exists and has the expected `sha1`, if it is known
+///
+/// The hash is compared only with [`FileCheck::Hash`]
+pub async fn is_file_valid(
+    path: &Path,
+    sha1: Option<&str>,
+    check: FileCheck,
+) -> Result<bool, Error> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    match (sha1, check) {
+        (Some(sha1), FileCheck::Hash) => {
+            Ok(utils::get_file_sha1(path).await?.eq_ignore_ascii_case(sha1))
+        }
+        _ => Ok(true),
+    }
+}
+
+/// Download library to `path` and verify its `sha1`, valid existing file is kept
+///
+/// The library is written to a temporary file first, so a broken download never
+/// replaces the file
+pub async fn download_library(
+    url: &str,
+    path: &Path,
+    sha1: Option<&str>,
+    check: FileCheck,
+    client: &reqwest::Client,
+) -> Result<(), Error> {
+    if is_file_valid(path, sha1, check).await? {
+        return Ok(());
+    }
+    if url.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Library {path:?} is missing and has no url"),
+        )
+        .into());
+    }
+
+    let bytes = helpers::http::get(url, Some(client)).await?;
+    if let Some(sha1) = sha1
+        && !utils::get_sha1(&bytes).eq_ignore_ascii_case(sha1)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Library {url} doesn't match sha1 {sha1}"),
+        )
+        .into());
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_path = path.with_file_name(format!("{file_name}.tmp"));
+    tokio::fs::write(&tmp_path, bytes).await?;
+    tokio::fs::rename(tmp_path, path).await?;
+    Ok(())
+}
+
 async fn install_lib(
     lib: &VersionJsonLibrary,
     game_dir: &Path,
     natives_dir: &Path,
+    check: FileCheck,
     client: &reqwest::Client,
 ) -> Result<(), Error> {
     if !lib.rules.is_empty() && !lib.check_rule_allow() {
@@ -50,12 +126,21 @@ async fn install_lib(
 
     let current_path = Path::new(game_dir).join("libraries");
 
-    let download_url = &lib.downloads.artifact.url;
-    let filename = &current_path.join(&lib.downloads.artifact.path);
+    let Some(artifact) = lib.get_artifact() else {
+        return Ok(());
+    };
+    let filename = &current_path.join(&artifact.path);
 
     tokio::fs::create_dir_all(&filename.parent().unwrap_or(&current_path)).await?;
 
-    download_file(download_url, filename, client).await?;
+    download_library(
+        &artifact.url,
+        filename,
+        artifact.sha1.as_deref(),
+        check,
+        client,
+    )
+    .await?;
 
     if !natives.is_empty() {
         utils::unzip(filename, natives_dir).await?;
@@ -63,7 +148,11 @@ async fn install_lib(
     Ok(())
 }
 
-pub async fn install_libraries(launcher: &Launcher, info: &VersionJson) -> Result<(), Error> {
+pub async fn install_libraries(
+    launcher: &Launcher,
+    info: &VersionJson,
+    check: FileCheck,
+) -> Result<(), Error> {
     let total_libs_count = info.libraries.len();
     log::info!("Installing [{}] libraries", total_libs_count);
     let client = reqwest::Client::builder().build()?;
@@ -75,7 +164,7 @@ pub async fn install_libraries(launcher: &Launcher, info: &VersionJson) -> Resul
     std::fs::create_dir_all(&natives_dir)?;
 
     for lib in info.libraries.iter() {
-        install_lib(lib, &launcher.path, &natives_dir, &client).await?;
+        install_lib(lib, &launcher.path, &natives_dir, check, &client).await?;
     }
     log::info!("Libs installed");
     Ok(())
@@ -109,9 +198,10 @@ pub async fn install_assets(launcher: &Launcher, info: &VersionJson) -> Result<(
 
 pub async fn install_client(launcher: &Launcher, info: &VersionJson) -> Result<(), Error> {
     log::info!("Installing client...");
-    let path = launcher.path.join("versions").join(&info.id);
+    let jar_id = info.get_jar_id();
+    let path = launcher.path.join("versions").join(jar_id);
     tokio::fs::create_dir_all(&path).await?;
-    let path = path.join(format!("{}.jar", info.id));
+    let path = path.join(format!("{jar_id}.jar"));
     if path.exists() {
         log::info!("Client already installed");
         return Ok(());
@@ -123,9 +213,262 @@ pub async fn install_client(launcher: &Launcher, info: &VersionJson) -> Result<(
     Ok(())
 }
 
+/// Get path of the client log4j config of the version
+pub fn get_logging_config_path(launcher: &Launcher, info: &VersionJson) -> Option<PathBuf> {
+    let config = info.logging.as_ref()?.client.as_ref()?;
+    let path = launcher
+        .path
+        .join("assets")
+        .join("log_configs")
+        .join(&config.file.id);
+    Some(path)
+}
+
+/// Install log4j config, so the game prints structured xml events
+pub async fn install_logging_config(launcher: &Launcher, info: &VersionJson) -> Result<(), Error> {
+    let (Some(path), Some(config)) = (
+        get_logging_config_path(launcher, info),
+        info.logging.as_ref().and_then(|i| i.client.as_ref()),
+    ) else {
+        return Ok(());
+    };
+
+    if path.exists() && utils::get_file_sha1(&path).await? == config.file.sha1 {
+        return Ok(());
+    }
+
+    log::info!("Installing logging config \"{}\"...", config.file.id);
+    tokio::fs::create_dir_all(path.parent().unwrap_or(&launcher.path)).await?;
+    let bytes = helpers::http::get(&config.file.url, None).await?;
+    tokio::fs::write(&path, bytes).await?;
+    Ok(())
+}
+
 impl Version {
     pub async fn get_info(&self, launcher: &Launcher) -> Result<VersionJson, Error> {
         let info = get_version_info(self, &launcher.path).await?;
         Ok(info)
     }
 }
+
+/// Get library name without version, e.g. `group:name:classifier`
+fn get_library_key(name: &str) -> String {
+    let parts: Vec<&str> = name.split(':').collect();
+    match parts.as_slice() {
+        [group, artifact, _, rest @ ..] => [&[*group, *artifact], rest].concat().join(":"),
+        _ => name.to_string(),
+    }
+}
+
+/// Merge inheriting version json `child` into its `parent`, like the official launcher does
+///
+/// Libraries of the child go first and replace the parent ones with the same name,
+/// arguments are appended, other fields of the child override the parent ones
+pub fn merge_version_json(parent: Value, child: Value) -> Value {
+    let (Value::Object(mut merged), Value::Object(child)) = (parent, child) else {
+        return Value::Null;
+    };
+
+    // Наследник использует jar родителя, если у него нет своего
+    if !child.contains_key("jar") {
+        let jar = merged.get("jar").or(merged.get("id")).cloned();
+        if let Some(jar) = jar {
+            merged.insert("jar".to_string(), jar);
+        }
+    }
+
+    for (key, value) in child {
+        match (key.as_str(), value) {
+            ("inheritsFrom", _) => {}
+            ("libraries", Value::Array(libraries)) => {
+                let keys: HashSet<String> = libraries
+                    .iter()
+                    .filter_map(|i| i["name"].as_str())
+                    .map(get_library_key)
+                    .collect();
+                let mut merged_libraries = libraries;
+                if let Some(Value::Array(parent)) = merged.remove("libraries") {
+                    merged_libraries.extend(parent.into_iter().filter(|i| {
+                        i["name"]
+                            .as_str()
+                            .is_none_or(|name| !keys.contains(&get_library_key(name)))
+                    }));
+                }
+                merged.insert(key, Value::Array(merged_libraries));
+            }
+            ("arguments", Value::Object(arguments)) => {
+                let merged_arguments = merged
+                    .entry("arguments")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                for (kind, values) in arguments {
+                    let Value::Array(values) = values else {
+                        continue;
+                    };
+                    match merged_arguments.get_mut(&kind) {
+                        Some(Value::Array(parent)) => parent.extend(values),
+                        _ => {
+                            merged_arguments[&kind] = Value::Array(values);
+                        }
+                    }
+                }
+            }
+            (_, value) => {
+                merged.insert(key, value);
+            }
+        }
+    }
+
+    Value::Object(merged)
+}
+
+/// Get raw version json by id with all of its parents merged in
+///
+/// `visited` holds ids of the inheriting versions, so a cycle is an error
+fn get_version_value<'a>(
+    launcher: &'a Launcher,
+    id: &'a str,
+    visited: &'a mut HashSet<String>,
+) -> BoxFuture<'a, Result<Value, Error>> {
+    Box::pin(async move {
+        if !visited.insert(id.to_string()) {
+            return Err(Error::Instance(format!(
+                "Version {id} inherits from itself"
+            )));
+        }
+        let path = launcher
+            .path
+            .join("versions")
+            .join(id)
+            .join(format!("{id}.json"));
+        let value: Value = if path.exists() {
+            serde_json::from_slice(&tokio::fs::read(&path).await?)?
+        } else {
+            let versions = utils::get_versions_list().await?;
+            let version = versions
+                .find_version(id)
+                .ok_or_else(|| Error::Instance(format!("Version {id} not found")))?;
+            log::info!("Downloading version manifest...");
+            let data = helpers::http::get(&version.url, None).await?;
+            tokio::fs::create_dir_all(path.parent().unwrap_or(&launcher.path)).await?;
+            tokio::fs::write(&path, &data).await?;
+            serde_json::from_slice(&data)?
+        };
+
+        let Some(parent_id) = value["inheritsFrom"].as_str() else {
+            return Ok(value);
+        };
+        let parent = get_version_value(launcher, parent_id, visited).await?;
+        Ok(merge_version_json(parent, value))
+    })
+}
+
+/// Get version json by id, the version manifest is downloaded only if it is not installed yet
+///
+/// Inheriting versions (e.g. mod loaders) are merged with their parents
+pub async fn get_version_json(launcher: &Launcher, id: &str) -> Result<VersionJson, Error> {
+    let value = get_version_value(launcher, id, &mut HashSet::new()).await?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Install libraries, assets, client and logging config of the version
+pub async fn install_version(
+    launcher: &Launcher,
+    info: &VersionJson,
+    check: FileCheck,
+) -> Result<(), Error> {
+    install_libraries(launcher, info, check).await?;
+    install_assets(launcher, info).await?;
+    install_client(launcher, info).await?;
+    install_logging_config(launcher, info).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::helpers::testing::TempLauncher;
+
+    fn write_version(temp: &TempLauncher, id: &str, value: Value) {
+        let dir = temp.path().join("versions").join(id);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{id}.json")), value.to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn inheritance_cycle_is_an_error() {
+        let temp = TempLauncher::new();
+        write_version(&temp, "a", json!({ "id": "a", "inheritsFrom": "b" }));
+        write_version(&temp, "b", json!({ "id": "b", "inheritsFrom": "a" }));
+        write_version(
+            &temp,
+            "self",
+            json!({ "id": "self", "inheritsFrom": "self" }),
+        );
+
+        for id in ["a", "self"] {
+            let result = get_version_value(&temp.launcher, id, &mut HashSet::new()).await;
+            let Err(Error::Instance(message)) = result else {
+                panic!("Cycle of {id} must be an error");
+            };
+            assert!(message.contains("inherits from itself"));
+        }
+    }
+
+    #[tokio::test]
+    async fn inheriting_version_is_merged() {
+        let temp = TempLauncher::new();
+        write_version(
+            &temp,
+            "1.21.8",
+            json!({ "id": "1.21.8", "mainClass": "net.minecraft.client.main.Main", "type": "release" }),
+        );
+        write_version(
+            &temp,
+            "fabric",
+            json!({ "id": "fabric", "inheritsFrom": "1.21.8", "mainClass": "net.fabricmc.Knot" }),
+        );
+
+        let value = get_version_value(&temp.launcher, "fabric", &mut HashSet::new())
+            .await
+            .unwrap();
+        assert_eq!(value["id"], "fabric");
+        assert_eq!(value["jar"], "1.21.8");
+        assert_eq!(value["mainClass"], "net.fabricmc.Knot");
+        assert_eq!(value["type"], "release");
+        assert!(value.get("inheritsFrom").is_none());
+    }
+
+    #[tokio::test]
+    async fn hash_is_checked_only_on_install() {
+        let temp = TempLauncher::new();
+        let path = temp.path().join("lib.jar");
+        let sha1 = utils::get_sha1(b"library");
+        assert!(
+            !is_file_valid(&path, Some(&sha1), FileCheck::Exists)
+                .await
+                .unwrap()
+        );
+
+        std::fs::write(&path, "broken").unwrap();
+        assert!(
+            is_file_valid(&path, Some(&sha1), FileCheck::Exists)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !is_file_valid(&path, Some(&sha1), FileCheck::Hash)
+                .await
+                .unwrap()
+        );
+        assert!(is_file_valid(&path, None, FileCheck::Hash).await.unwrap());
+
+        std::fs::write(&path, "library").unwrap();
+        assert!(
+            is_file_valid(&path, Some(&sha1.to_uppercase()), FileCheck::Hash)
+                .await
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use super::{
+    GAME_DIR, Instance, LOCK_FILE, MANIFEST_FILE, SAVES_DIR, get_instances_dir, install_instance,
+    load_instance, validate_instance_name,
+};
+use crate::types::{Error, Launcher};
+
+/// Game directory entries which are never exported
+const EXCLUDED_ENTRIES: &[&str] = &[LOCK_FILE, "logs", "crash-reports"];
+
+/// Shared game files, which are never exported or cloned
+///
+/// Game directory of the profile imported from the official launcher is its
+/// `.minecraft` with versions, libraries and assets of all profiles
+pub const SHARED_ENTRIES: &[&str] = &["versions", "libraries", "assets", "runtime"];
+
+/// Check if the top level game directory entry is left out of exports
+pub fn is_excluded_entry(name: &OsStr) -> bool {
+    EXCLUDED_ENTRIES
+        .iter()
+        .chain(SHARED_ENTRIES)
+        .any(|i| name == *i)
+}
+
+/// What to put into the exported archive besides the manifest, mods and configs
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Names of the worlds in `saves`
+    pub saves: Vec<String>,
+}
+
+fn get_zip_name(path: &Path) -> String {
+    path.components()
+        .map(|i| i.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Add files of `dir` under `prefix`, `filter` gets paths relative to `root`
+pub fn add_dir(
+    zip: &mut ZipWriter<fs::File>,
+    dir: &Path,
+    root: &Path,
+    prefix: &str,
+    filter: &dyn Fn(&Path) -> bool,
+) -> Result<(), Error> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        if !filter(relative) {
+            continue;
+        }
+
+        let name = format!("{prefix}/{}", get_zip_name(relative));
+        if entry.file_type()?.is_dir() {
+            zip.add_directory(format!("{name}/"), options)?;
+            add_dir(zip, &path, root, prefix, filter)?;
+        } else {
+            zip.start_file(name, options)?;
+            io::copy(&mut fs::File::open(&path)?, zip)?;
+        }
+    }
+    Ok(())
+}
+
+/// Export instance into zip archive at `dest`
+///
+/// Archive contains the instance manifest and the game directory,
+/// shared libraries, assets and runtimes are left out
+pub fn export_instance(
+    launcher: &Launcher,
+    name: &str,
+    dest: &Path,
+    options: &ExportOptions,
+) -> Result<(), Error> {
+    let instance = load_instance(launcher, name)?;
+    for save in &options.saves {
+        if !instance.get_game_dir().join(SAVES_DIR).join(save).is_dir() {
+            return Err(Error::Instance(format!(
+                "World {save} not found in instance {name}"
+            )));
+        }
+    }
+
+    log::info!("Exporting instance {name} to {dest:?}...");
+    let mut zip = ZipWriter::new(fs::File::create(dest)?);
+
+    let manifest_options =
+        SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // Импортированный инстанс всегда получает собственную директорию игры
+    let manifest = Instance {
+        game_dir: None,
+        profile_id: None,
+        ..instance.clone()
+    };
+    zip.start_file(MANIFEST_FILE, manifest_options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+
+    let game_dir = instance.get_game_dir();
+    if game_dir.exists() {
+        zip.add_directory(format!("{GAME_DIR}/"), manifest_options)?;
+        add_dir(&mut zip, &game_dir, &game_dir, GAME_DIR, &|relative| {
+            let mut components = relative.components();
+            let Some(Component::Normal(first)) = components.next() else {
+                return false;
+            };
+            if is_excluded_entry(first) {
+                return false;
+            }
+            if first != SAVES_DIR {
+                return true;
+            }
+            // В архив попадают только выбранные миры
+            match components.next() {
+                Some(Component::Normal(save)) => options.saves.iter().any(|i| save == i.as_str()),
+                _ => true,
+            }
+        })?;
+    }
+
+    zip.finish()?;
+    log::info!("Instance {name} exported!");
+    Ok(())
+}
+
+/// Read instance manifest from the exported archive
+pub fn read_archive_manifest(path: &Path) -> Result<Instance, Error> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+    let mut file = archive
+        .by_name(MANIFEST_FILE)
+        .map_err(|_| Error::Instance(format!("{path:?} is not an instance archive")))?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Import instance exported with [`export_instance`]
+///
+/// `name` overrides the name from the manifest. Version and runtime
+/// of the instance are installed after the files are extracted
+pub async fn import_instance(
+    launcher: &Launcher,
+    path: &Path,
+    name: Option<&str>,
+) -> Result<Instance, Error> {
+    let manifest = read_archive_manifest(path)?;
+    let name = name.unwrap_or(&manifest.name);
+    validate_instance_name(name)?;
+
+    let instance_dir = get_instances_dir(launcher).join(name);
+    if instance_dir.exists() {
+        return Err(Error::Instance(format!("Instance {name} already exists")));
+    }
+
+    log::info!("Importing instance {name} from {path:?}...");
+    if let Err(err) = extract_instance(path, &instance_dir) {
+        let _ = fs::remove_dir_all(&instance_dir);
+        return Err(err);
+    }
+
+    let mut instance = load_instance(launcher, name)?;
+    instance.game_dir = None;
+    instance.profile_id = None;
+    // Имя в манифесте могло отличаться от выбранного
+    instance.save()?;
+
+    let info = install_instance(launcher, &instance).await?;
+    instance.get_java(launcher, &info).await?;
+
+    log::info!("Instance {name} imported!");
+    Ok(instance)
+}
+
+fn extract_instance(path: &Path, instance_dir: &Path) -> Result<(), Error> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+    fs::create_dir_all(instance_dir)?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let Some(relative) = file.enclosed_name() else {
+            continue;
+        };
+        // Все, кроме манифеста и директории игры, игнорируется
+        if relative != Path::new(MANIFEST_FILE) && !relative.starts_with(GAME_DIR) {
+            continue;
+        }
+
+        let outpath: PathBuf = instance_dir.join(relative);
+        if file.is_dir() {
+            fs::create_dir_all(&outpath)?;
+            continue;
+        }
+        if let Some(parent) = outpath.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut fs::File::create(&outpath)?)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::testing::TempLauncher,
+        instances::{clone_instance, create_instance},
+    };
+
+    /// Instance using a shared game directory, like the official launcher profiles
+    fn create_shared_instance(temp: &TempLauncher) -> PathBuf {
+        let shared = temp.path().join("official");
+        for dir in ["versions/1.21.8", "libraries/org", "assets/objects", "mods"] {
+            fs::create_dir_all(shared.join(dir)).unwrap();
+        }
+        fs::write(shared.join("versions/1.21.8/1.21.8.jar"), b"jar").unwrap();
+        fs::write(shared.join("mods/a.jar"), b"mod").unwrap();
+        fs::write(shared.join("options.txt"), b"fov:1").unwrap();
+
+        let mut instance = create_instance(&temp.launcher, "shared", "1.21.8").unwrap();
+        instance.game_dir = Some(shared.clone());
+        instance.save().unwrap();
+        shared
+    }
+
+    #[test]
+    fn shared_game_files_are_not_exported() {
+        let temp = TempLauncher::new();
+        create_shared_instance(&temp);
+
+        let dest = temp.path().join("shared.zip");
+        export_instance(&temp.launcher, "shared", &dest, &ExportOptions::default()).unwrap();
+        let archive = ZipArchive::new(fs::File::open(&dest).unwrap()).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert!(names.contains(&".minecraft/mods/a.jar"));
+        assert!(names.contains(&".minecraft/options.txt"));
+        assert!(!names.iter().any(|i| i.starts_with(".minecraft/versions")
+            || i.starts_with(".minecraft/libraries")
+            || i.starts_with(".minecraft/assets")));
+
+        let clone = clone_instance(&temp.launcher, "shared", "clone", true).unwrap();
+        let game_dir = clone.get_game_dir();
+        assert!(game_dir.join("mods").join("a.jar").exists());
+        assert!(game_dir.join("options.txt").exists());
+        for dir in ["versions", "libraries", "assets"] {
+            assert!(!game_dir.join(dir).exists(), "{dir} is cloned");
+        }
+    }
+}
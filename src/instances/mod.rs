@@ -0,0 +1,391 @@
+pub mod archive;
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::AuthInfo,
+    install::{self, FileCheck},
+    internal_types::shared::VersionJson,
+    jvm::{
+        self,
+        discovery::{self, JavaInstallation},
+        manage,
+    },
+    loaders,
+    mods::InstalledMod,
+    process::GameProcess,
+    runtime, sessions,
+    types::{Error, LaunchOptions, Launcher},
+    utils,
+};
+
+const INSTANCES_DIR: &str = "instances";
+const MANIFEST_FILE: &str = "instance.json";
+const GAME_DIR: &str = ".minecraft";
+pub const SAVES_DIR: &str = "saves";
+const LOCK_FILE: &str = ".launcher.lock";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoaderKind {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Loader {
+    pub kind: LoaderKind,
+    pub version: String,
+}
+
+/// Java used to run the instance
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JavaChoice {
+    /// Installed java or runtime required by the version
+    #[default]
+    Auto,
+    /// Runtime managed by the launcher, e.g. `java-runtime-delta` or `temurin-21`
+    Runtime { component: String },
+    /// Path to the java executable
+    Path { path: PathBuf },
+}
+
+/// Game instance with its own game directory and settings
+///
+/// Stored as `instances/<name>/instance.json`, the game directory is
+/// `instances/<name>/.minecraft`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instance {
+    pub name: String,
+    /// Minecraft version id
+    pub version: String,
+    pub loader: Option<Loader>,
+    #[serde(default)]
+    pub java: JavaChoice,
+    #[serde(default)]
+    pub options: LaunchOptions,
+    /// Seconds since the unix epoch
+    pub created_at: u64,
+    /// Seconds since the unix epoch
+    pub last_played: Option<u64>,
+    /// Custom game directory, e.g. shared with the official launcher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_dir: Option<PathBuf>,
+    /// Key of the official launcher profile the instance was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<String>,
+    /// Mods installed from mod platforms
+    #[serde(default)]
+    pub mods: Vec<InstalledMod>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+pub fn get_instances_dir(launcher: &Launcher) -> PathBuf {
+    launcher.path.join(INSTANCES_DIR)
+}
+
+/// Check that `name` can be used as a directory name
+pub fn validate_instance_name(name: &str) -> Result<(), Error> {
+    let is_valid = !name.trim().is_empty()
+        && name != "."
+        && name != ".."
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|'])
+        && !name.chars().any(char::is_control);
+    if !is_valid {
+        return Err(Error::Instance(format!("Invalid instance name {name:?}")));
+    }
+    Ok(())
+}
+
+impl Instance {
+    /// Directory with the instance manifest
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Directory with saves, mods, configs and resource packs
+    pub fn get_game_dir(&self) -> PathBuf {
+        match &self.game_dir {
+            Some(dir) => dir.clone(),
+            None => self.path.join(GAME_DIR),
+        }
+    }
+
+    /// Write the manifest atomically
+    pub fn save(&self) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.path)?;
+        let tmp_path = self.path.join(format!("{MANIFEST_FILE}.tmp"));
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(tmp_path, self.path.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+
+    /// Get java according to the instance java choice
+    pub async fn get_java(
+        &self,
+        launcher: &Launcher,
+        info: &VersionJson,
+    ) -> Result<JavaInstallation, Error> {
+        match &self.java {
+            JavaChoice::Auto => jvm::get_java(launcher, info).await,
+            JavaChoice::Runtime { component } => {
+                let home = manage::install_runtime(launcher, component).await?;
+                let path = discovery::get_java_from_home(&home)
+                    .ok_or_else(|| Error::Jvm(format!("Java executable not found in {home:?}")))?;
+                discovery::probe_java(&path).await
+            }
+            JavaChoice::Path { path } => discovery::probe_java(path).await,
+        }
+    }
+}
+
+/// Create new instance of the Minecraft `version`
+pub fn create_instance(launcher: &Launcher, name: &str, version: &str) -> Result<Instance, Error> {
+    validate_instance_name(name)?;
+    let path = get_instances_dir(launcher).join(name);
+    if path.exists() {
+        return Err(Error::Instance(format!("Instance {name} already exists")));
+    }
+
+    log::info!("Creating instance {name}...");
+    let instance = Instance {
+        name: name.to_string(),
+        version: version.to_string(),
+        loader: None,
+        java: JavaChoice::Auto,
+        options: LaunchOptions::default(),
+        created_at: utils::get_timestamp(),
+        last_played: None,
+        game_dir: None,
+        profile_id: None,
+        mods: Vec::new(),
+        path,
+    };
+    std::fs::create_dir_all(instance.get_game_dir())?;
+    instance.save()?;
+
+    Ok(instance)
+}
+
+pub fn load_instance(launcher: &Launcher, name: &str) -> Result<Instance, Error> {
+    validate_instance_name(name)?;
+    let path = get_instances_dir(launcher).join(name);
+    let data = match std::fs::read(path.join(MANIFEST_FILE)) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(Error::Instance(format!("Instance {name} not found")));
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut instance: Instance = serde_json::from_slice(&data)?;
+    // Имя определяется директорией, даже если манифест переносили вручную
+    instance.name = name.to_string();
+    instance.path = path;
+    Ok(instance)
+}
+
+/// List instances, broken manifests are skipped
+pub fn list_instances(launcher: &Launcher) -> Result<Vec<Instance>, Error> {
+    let entries = match std::fs::read_dir(get_instances_dir(launcher)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut instances = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        match load_instance(launcher, &name) {
+            Ok(instance) => instances.push(instance),
+            Err(err) => log::warn!("Skipping instance {name}: {err:?}"),
+        }
+    }
+
+    instances.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(instances)
+}
+
+/// Check if the game of the instance is running
+pub fn is_instance_running(launcher: &Launcher, instance: &Instance) -> Result<bool, Error> {
+    let has_session = sessions::list_sessions(launcher)?
+        .iter()
+        .any(|session| session.instance.as_deref() == Some(instance.name.as_str()));
+    // Игра могла быть запущена другим лаунчером без регистрации сессии
+    let is_locked = instance.get_game_dir().exists()
+        && sessions::lock_game_dir(&instance.get_game_dir()).is_err();
+    Ok(has_session || is_locked)
+}
+
+fn check_not_running(launcher: &Launcher, instance: &Instance) -> Result<(), Error> {
+    if is_instance_running(launcher, instance)? {
+        return Err(Error::Instance(format!(
+            "Instance {} is running",
+            instance.name
+        )));
+    }
+    Ok(())
+}
+
+/// Copy instance `name` as `new_name`, saves are copied only if `include_saves` is set
+pub fn clone_instance(
+    launcher: &Launcher,
+    name: &str,
+    new_name: &str,
+    include_saves: bool,
+) -> Result<Instance, Error> {
+    let instance = load_instance(launcher, name)?;
+    validate_instance_name(new_name)?;
+    let path = get_instances_dir(launcher).join(new_name);
+    if path.exists() {
+        return Err(Error::Instance(format!(
+            "Instance {new_name} already exists"
+        )));
+    }
+
+    log::info!("Cloning instance {name} as {new_name}...");
+    let game_dir = instance.get_game_dir();
+    let saves_dir = game_dir.join(SAVES_DIR);
+    utils::copy_dir(&instance.path, &path, &|entry| {
+        entry != game_dir && entry.file_name().is_some_and(|i| i != LOCK_FILE)
+    })?;
+    // Клон всегда получает собственную директорию игры
+    if game_dir.exists() {
+        utils::copy_dir(&game_dir, &path.join(GAME_DIR), &|entry| {
+            let is_excluded = entry.parent() == Some(game_dir.as_path())
+                && entry
+                    .file_name()
+                    .is_some_and(|name| archive::SHARED_ENTRIES.iter().any(|i| name == *i));
+            entry.file_name().is_some_and(|i| i != LOCK_FILE)
+                && !is_excluded
+                && (include_saves || entry != saves_dir)
+        })?;
+    }
+
+    let clone = Instance {
+        name: new_name.to_string(),
+        created_at: utils::get_timestamp(),
+        last_played: None,
+        game_dir: None,
+        profile_id: None,
+        path,
+        ..instance
+    };
+    std::fs::create_dir_all(clone.get_game_dir())?;
+    clone.save()?;
+
+    Ok(clone)
+}
+
+pub fn rename_instance(launcher: &Launcher, name: &str, new_name: &str) -> Result<Instance, Error> {
+    let instance = load_instance(launcher, name)?;
+    validate_instance_name(new_name)?;
+    let path = get_instances_dir(launcher).join(new_name);
+    if path.exists() {
+        return Err(Error::Instance(format!(
+            "Instance {new_name} already exists"
+        )));
+    }
+    check_not_running(launcher, &instance)?;
+
+    log::info!("Renaming instance {name} to {new_name}...");
+    std::fs::rename(&instance.path, &path)?;
+    let instance = Instance {
+        name: new_name.to_string(),
+        path,
+        ..instance
+    };
+    instance.save()?;
+
+    Ok(instance)
+}
+
+/// Delete instance with all of its saves
+///
+/// `confirmation` must be equal to the instance name, running instances can't be deleted.
+/// Custom game directory is not deleted
+pub fn delete_instance(launcher: &Launcher, name: &str, confirmation: &str) -> Result<(), Error> {
+    let instance = load_instance(launcher, name)?;
+    if confirmation != instance.name {
+        return Err(Error::Instance(format!(
+            "Deletion of instance {name} is not confirmed"
+        )));
+    }
+    check_not_running(launcher, &instance)?;
+
+    log::info!("Deleting instance {name}...");
+    std::fs::remove_dir_all(&instance.path)?;
+    Ok(())
+}
+
+/// Install the version and loader of the instance, existing files are checked with `check`
+async fn prepare_instance(
+    launcher: &Launcher,
+    instance: &Instance,
+    check: FileCheck,
+) -> Result<VersionJson, Error> {
+    let id = match &instance.loader {
+        Some(loader) => loaders::install_loader(launcher, &instance.version, loader).await?,
+        None => instance.version.clone(),
+    };
+    let info = install::get_version_json(launcher, &id).await?;
+    install::install_version(launcher, &info, check).await?;
+    std::fs::create_dir_all(instance.get_game_dir())?;
+    Ok(info)
+}
+
+/// Install the instance version and loader into the shared store
+///
+/// Installed libraries are hashed again, so broken ones are repaired
+pub async fn install_instance(
+    launcher: &Launcher,
+    instance: &Instance,
+) -> Result<VersionJson, Error> {
+    prepare_instance(launcher, instance, FileCheck::Hash).await
+}
+
+/// Install and start the instance
+///
+/// Game directory stays locked and the session stays registered until the game exits
+pub async fn launch_instance(
+    launcher: &Launcher,
+    instance: &mut Instance,
+    auth: &AuthInfo,
+) -> Result<GameProcess, Error> {
+    // Библиотеки уже проверены при установке, на запуске достаточно их наличия
+    let info = prepare_instance(launcher, instance, FileCheck::Exists).await?;
+    let java = instance.get_java(launcher, &info).await?;
+
+    let game_dir = instance.get_game_dir();
+    let lock = sessions::run_blocking({
+        let (path, game_dir) = (launcher.path.clone(), game_dir.clone());
+        move || sessions::acquire_game_dir(&Launcher { path }, &game_dir)
+    })
+    .await?;
+    let process = runtime::launch(launcher, &game_dir, &info, &java, &instance.options, auth)?;
+    sessions::track_session(
+        launcher,
+        &process,
+        lock,
+        &info.id,
+        Some(&instance.name),
+        Some(&auth.name),
+    )
+    .await?;
+
+    instance.last_played = Some(utils::get_timestamp());
+    instance.save()?;
+
+    Ok(process)
+}
@@ -1,2 +1,9 @@
 pub mod shared;
+pub mod shared_auth;
 pub mod shared_jvm;
+pub mod shared_profiles;
+pub mod shared_multimc;
+pub mod shared_loaders;
+pub mod shared_forge;
+pub mod shared_modrinth;
+pub mod shared_curseforge;
@@ -4,51 +4,115 @@ use serde::Deserialize;
 
 use crate::{types::Platform, utils};
 
+/// Maven repository of the libraries without explicit url
+const DEFAULT_MAVEN_URL: &str = "https://libraries.minecraft.net/";
+
 #[derive(Debug, Deserialize)]
 pub struct _VersionJsonLibraryDownloadsArtifact {
     pub path: String,
     pub url: String,
+    pub sha1: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct _VersionJsonLibraryDownloads {
-    pub artifact: _VersionJsonLibraryDownloadsArtifact,
+    pub artifact: Option<_VersionJsonLibraryDownloadsArtifact>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct _VersionJsonLibraryRuleOs {
-    pub name: String,
+pub struct _VersionJsonRuleOs {
+    pub name: Option<String>,
+    pub arch: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct _VersionJsonLibraryRule {
+pub struct _VersionJsonRule {
     pub action: String,
-    pub os: _VersionJsonLibraryRuleOs,
+    pub os: Option<_VersionJsonRuleOs>,
+    #[serde(default)]
+    pub features: HashMap<String, bool>,
 }
 
-impl _VersionJsonLibraryRule {
-    fn parse_rule(&self) -> bool {
-        let platform = utils::get_platform();
-        let val = self.action != "allow";
-
-        if self.os.name == "windows" && !matches!(platform, Platform::Windows) {
-            return val;
-        } else if self.os.name == "osx" && !matches!(platform, Platform::Darwin) {
-            return val;
-        } else if self.os.name == "linux" && !matches!(platform, Platform::Linux) {
-            return val;
+impl _VersionJsonRule {
+    /// Check if the rule applies to the current platform and `features`
+    fn is_matching(&self, features: &[&str]) -> bool {
+        if let Some(os) = &self.os {
+            let platform = utils::get_platform();
+            let is_platform = match os.name.as_deref() {
+                Some("windows") => matches!(platform, Platform::Windows),
+                Some("osx") => matches!(platform, Platform::Darwin),
+                Some("linux") => matches!(platform, Platform::Linux),
+                Some(_) => false,
+                None => true,
+            };
+            if !is_platform {
+                return false;
+            }
+            if let Some(arch) = &os.arch
+                && arch != utils::get_arch()
+            {
+                return false;
+            }
         }
 
-        !val
+        self.features
+            .iter()
+            .all(|(feature, value)| features.contains(&feature.as_str()) == *value)
+    }
+}
+
+/// Evaluate rules the same way as the official launcher:
+/// the last matching rule wins, nothing is allowed if no rule matches
+pub fn check_rules(rules: &[_VersionJsonRule], features: &[&str]) -> bool {
+    if rules.is_empty() {
+        return true;
+    }
+
+    let mut allow = false;
+    for rule in rules {
+        if rule.is_matching(features) {
+            allow = rule.action == "allow";
+        }
     }
+    allow
 }
 
 #[derive(Debug, Deserialize)]
 pub struct VersionJsonLibrary {
     pub name: String,
-    pub downloads: _VersionJsonLibraryDownloads,
+    pub downloads: Option<_VersionJsonLibraryDownloads>,
+    /// Maven repository of the library without `downloads`, e.g. Fabric libraries
+    pub url: Option<String>,
+    pub sha1: Option<String>,
     #[serde(default)]
-    pub rules: Vec<_VersionJsonLibraryRule>,
+    pub rules: Vec<_VersionJsonRule>,
+}
+
+/// Library file to download
+#[derive(Debug)]
+pub struct LibraryArtifact {
+    /// Path relative to the `libraries` directory
+    pub path: String,
+    pub url: String,
+    pub sha1: Option<String>,
+}
+
+/// Get path of the maven artifact, e.g. `group:name:version[:classifier][@extension]`
+pub fn get_maven_path(name: &str) -> Option<String> {
+    let (name, extension) = name.split_once('@').unwrap_or((name, "jar"));
+    let mut parts = name.split(':');
+    let (Some(group), Some(artifact), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    let file = match parts.next() {
+        Some(classifier) => format!("{artifact}-{version}-{classifier}.{extension}"),
+        None => format!("{artifact}-{version}.{extension}"),
+    };
+    Some(format!(
+        "{}/{artifact}/{version}/{file}",
+        group.replace('.', "/")
+    ))
 }
 
 impl VersionJsonLibrary {
@@ -62,12 +126,27 @@ impl VersionJsonLibrary {
     }
 
     pub fn check_rule_allow(&self) -> bool {
-        for i in self.rules.iter() {
-            if !i.parse_rule() {
-                return false;
-            }
+        check_rules(&self.rules, &[])
+    }
+
+    /// Get library file from `downloads.artifact`, or from its maven coordinates and `url`
+    pub fn get_artifact(&self) -> Option<LibraryArtifact> {
+        if let Some(downloads) = &self.downloads {
+            let artifact = downloads.artifact.as_ref()?;
+            return Some(LibraryArtifact {
+                path: artifact.path.clone(),
+                url: artifact.url.clone(),
+                sha1: artifact.sha1.clone(),
+            });
         }
-        true
+
+        let path = get_maven_path(&self.name)?;
+        let repository = self.url.as_deref().unwrap_or(DEFAULT_MAVEN_URL);
+        Some(LibraryArtifact {
+            url: format!("{}/{path}", repository.trim_end_matches('/')),
+            path,
+            sha1: self.sha1.clone(),
+        })
     }
 }
 
@@ -88,16 +167,78 @@ pub struct _JsonJavaVersion {
     pub major_version: u16,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum _JsonVersionArgValue {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum _JsonVersionArg {
+    Plain(String),
+    Ruled {
+        rules: Vec<_VersionJsonRule>,
+        value: _JsonVersionArgValue,
+    },
+}
+
+impl _JsonVersionArg {
+    /// Get values of the argument allowed for `features`
+    pub fn get_values(&self, features: &[&str]) -> Vec<&str> {
+        match self {
+            _JsonVersionArg::Plain(value) => vec![value],
+            _JsonVersionArg::Ruled { rules, value } => {
+                if !check_rules(rules, features) {
+                    return Vec::new();
+                }
+                match value {
+                    _JsonVersionArgValue::Single(value) => vec![value],
+                    _JsonVersionArgValue::Multiple(values) => {
+                        values.iter().map(String::as_str).collect()
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct _JsonVersionArgs {
-    pub game: Vec<String>,
-    pub jvm: Vec<String>,
+    #[serde(default)]
+    pub game: Vec<_JsonVersionArg>,
+    #[serde(default)]
+    pub jvm: Vec<_JsonVersionArg>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonLoggingFile {
+    pub id: String,
+    pub sha1: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonLoggingConfig {
+    pub argument: String,
+    pub file: _JsonLoggingFile,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonLogging {
+    pub client: Option<_JsonLoggingConfig>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct VersionJson {
-    // pub arguments: _JsonVersionArgs,
+    pub arguments: Option<_JsonVersionArgs>,
+    /// Arguments of versions before 1.13
+    #[serde(rename = "minecraftArguments")]
+    pub minecraft_arguments: Option<String>,
     pub id: String,
+    /// Id of the version whose client jar is used, set for the inheriting versions
+    pub jar: Option<String>,
     pub assets: String,
     #[serde(rename = "assetIndex")]
     pub asset_index: _JsonDownloadItem,
@@ -115,6 +256,15 @@ pub struct VersionJson {
     pub java_version: _JsonJavaVersion,
 
     pub libraries: Vec<VersionJsonLibrary>,
+
+    pub logging: Option<_JsonLogging>,
+}
+
+impl VersionJson {
+    /// Get id of the version whose client jar is used
+    pub fn get_jar_id(&self) -> &str {
+        self.jar.as_deref().unwrap_or(&self.id)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonDeviceCode {
+    pub user_code: String,
+    pub device_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+    #[serde(default)]
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct JsonOAuthToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsonOAuthError {
+    pub error: String,
+    #[serde(default)]
+    pub error_description: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonXboxUserClaim {
+    pub uhs: String,
+    pub xid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonXboxDisplayClaims {
+    pub xui: Vec<_JsonXboxUserClaim>,
+}
+
+#[derive(Deserialize)]
+pub struct JsonXboxToken {
+    #[serde(rename = "Token")]
+    pub token: String,
+    #[serde(rename = "DisplayClaims")]
+    pub display_claims: _JsonXboxDisplayClaims,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsonXboxError {
+    #[serde(rename = "XErr")]
+    pub xerr: u64,
+    #[serde(rename = "Message", default)]
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct JsonMinecraftToken {
+    pub access_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonEntitlementItem {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsonEntitlements {
+    #[serde(default)]
+    pub items: Vec<_JsonEntitlementItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct _JsonProfileSkin {
+    pub id: String,
+    pub state: String,
+    pub url: String,
+    #[serde(default)]
+    pub variant: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsonMinecraftProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub skins: Vec<_JsonProfileSkin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct _JsonYggdrasilProfile {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonYggdrasilAuth {
+    pub access_token: String,
+    pub client_token: String,
+    #[serde(default)]
+    pub available_profiles: Vec<_JsonYggdrasilProfile>,
+    pub selected_profile: Option<_JsonYggdrasilProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonYggdrasilError {
+    pub error: String,
+    #[serde(default)]
+    pub error_message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonAuthlibInjectorChecksums {
+    pub sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsonAuthlibInjectorArtifact {
+    pub version: String,
+    pub download_url: String,
+    pub checksums: _JsonAuthlibInjectorChecksums,
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonCurseForgeModLoader {
+    /// Loader with its version, e.g. `forge-47.2.0`
+    pub id: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct _JsonCurseForgeMinecraft {
+    pub version: String,
+    #[serde(default)]
+    pub mod_loaders: Vec<_JsonCurseForgeModLoader>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonCurseForgeManifestFile {
+    #[serde(rename = "projectID")]
+    pub project_id: u64,
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// `manifest.json` of the CurseForge modpack
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCurseForgeManifest {
+    pub minecraft: _JsonCurseForgeMinecraft,
+    pub manifest_type: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub files: Vec<_JsonCurseForgeManifestFile>,
+    /// Directory with the game files
+    #[serde(default = "default_overrides")]
+    pub overrides: String,
+}
+
+fn default_overrides() -> String {
+    "overrides".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonCurseForgeHash {
+    pub value: String,
+    /// 1 is sha1, 2 is md5
+    pub algo: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCurseForgeFile {
+    pub id: u64,
+    pub mod_id: u64,
+    pub file_name: String,
+    /// Missing if the author disallowed third-party downloads
+    pub download_url: Option<String>,
+    #[serde(default)]
+    pub hashes: Vec<_JsonCurseForgeHash>,
+}
+
+impl JsonCurseForgeFile {
+    fn get_hash(&self, algo: u32) -> Option<&str> {
+        self.hashes
+            .iter()
+            .find(|i| i.algo == algo)
+            .map(|i| i.value.as_str())
+    }
+
+    pub fn get_sha1(&self) -> Option<&str> {
+        self.get_hash(1)
+    }
+
+    pub fn get_md5(&self) -> Option<&str> {
+        self.get_hash(2)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct _JsonCurseForgeModLinks {
+    pub website_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCurseForgeMod {
+    pub id: u64,
+    pub name: String,
+    pub slug: String,
+    /// Project class: mods, resource packs, shaders...
+    pub class_id: Option<u64>,
+    pub links: Option<_JsonCurseForgeModLinks>,
+}
+
+/// Response of the CurseForge api, the result is wrapped into `data`
+#[derive(Debug, Deserialize)]
+pub struct JsonCurseForgeResponse<T> {
+    pub data: T,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCurseForgeFilesRequest {
+    pub file_ids: Vec<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCurseForgeModsRequest {
+    pub mod_ids: Vec<u64>,
+}
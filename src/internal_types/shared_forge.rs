@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::shared::VersionJsonLibrary;
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonInstallProfileData {
+    pub client: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonInstallProfileProcessor {
+    /// Maven coordinates of the processor jar
+    pub jar: String,
+    #[serde(default)]
+    pub classpath: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Output file and its expected sha1, both may reference `data` entries
+    #[serde(default)]
+    pub outputs: HashMap<String, String>,
+    /// Processors without sides are run for both sides
+    pub sides: Option<Vec<String>>,
+}
+
+/// `install_profile.json` of the Forge / NeoForge installer for 1.13+
+#[derive(Debug, Deserialize)]
+pub struct JsonInstallProfile {
+    pub minecraft: String,
+    /// Path of the version json inside of the installer
+    pub json: String,
+    #[serde(default)]
+    pub data: HashMap<String, _JsonInstallProfileData>,
+    #[serde(default)]
+    pub processors: Vec<_JsonInstallProfileProcessor>,
+    #[serde(default)]
+    pub libraries: Vec<VersionJsonLibrary>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct _JsonLegacyInstall {
+    /// Maven coordinates of the universal jar
+    pub path: String,
+    /// Path of the universal jar inside of the installer
+    pub file_path: String,
+    pub minecraft: String,
+}
+
+/// `install_profile.json` of the legacy Forge installer, before 1.13
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonLegacyInstallProfile {
+    pub install: _JsonLegacyInstall,
+    pub version_info: Value,
+}
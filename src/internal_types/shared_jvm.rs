@@ -10,13 +10,22 @@ pub struct JavaRuntimesManifest {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct _JavaRuntimesManifestItem {
     pub manifest: _JavaRuntimesManifestItemValue,
+    pub version: _JavaRuntimesManifestItemVersion,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct _JavaRuntimesManifestItemValue {
+    pub sha1: String,
+    pub size: u64,
     pub url: String,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct _JavaRuntimesManifestItemVersion {
+    pub name: String,
+    pub released: String,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct JavaRuntimeFiles {
     pub files: HashMap<String, _JavaRuntimeFilesItem>,
@@ -41,3 +50,26 @@ pub struct _JavaRuntimeFilesItem {
 pub struct _JavaRuntimeFilesItemDownloads {
     pub raw: _JavaRuntimesManifestItemValue,
 }
+
+/// Release of `/v3/assets/latest/<major>/hotspot` Adoptium API
+#[derive(Clone, Deserialize)]
+pub struct JsonAdoptiumAsset {
+    pub binary: _JsonAdoptiumBinary,
+    pub release_name: String,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct _JsonAdoptiumBinary {
+    pub os: String,
+    pub architecture: String,
+    pub image_type: String,
+    pub package: _JsonAdoptiumPackage,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct _JsonAdoptiumPackage {
+    pub name: String,
+    pub link: String,
+    /// sha256 of the archive
+    pub checksum: String,
+}
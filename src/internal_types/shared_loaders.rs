@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct _JsonMetaLoader {
+    pub version: String,
+    /// Missing in Quilt Meta
+    pub stable: Option<bool>,
+}
+
+/// Item of `/versions/loader/<game_version>` of Fabric Meta-compatible apis
+#[derive(Debug, Deserialize)]
+pub struct JsonMetaLoaderVersion {
+    pub loader: _JsonMetaLoader,
+}
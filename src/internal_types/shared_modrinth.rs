@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct _JsonModrinthSearchHit {
+    pub project_id: String,
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub project_type: String,
+    #[serde(default)]
+    pub downloads: u64,
+    /// Supported game versions
+    #[serde(default)]
+    pub versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsonModrinthSearch {
+    pub hits: Vec<_JsonModrinthSearchHit>,
+    pub offset: u32,
+    pub limit: u32,
+    pub total_hits: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct _JsonModrinthHashes {
+    pub sha1: String,
+    pub sha512: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct _JsonModrinthFile {
+    pub hashes: _JsonModrinthHashes,
+    pub url: String,
+    pub filename: String,
+    #[serde(default)]
+    pub primary: bool,
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct _JsonModrinthDependency {
+    pub version_id: Option<String>,
+    pub project_id: Option<String>,
+    pub file_name: Option<String>,
+    /// `required`, `optional`, `incompatible` or `embedded`
+    pub dependency_type: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonModrinthVersion {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    /// `release`, `beta` or `alpha`
+    pub version_type: String,
+    pub files: Vec<_JsonModrinthFile>,
+    #[serde(default)]
+    pub dependencies: Vec<_JsonModrinthDependency>,
+}
+
+impl JsonModrinthVersion {
+    /// Primary file of the version, the first one if none is marked
+    pub fn get_primary_file(&self) -> Option<&_JsonModrinthFile> {
+        self.files
+            .iter()
+            .find(|i| i.primary)
+            .or_else(|| self.files.first())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct _JsonMrpackEnv {
+    /// `required`, `optional` or `unsupported`
+    pub client: String,
+    pub server: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct _JsonMrpackFile {
+    /// Path relative to the game directory
+    pub path: String,
+    pub hashes: _JsonModrinthHashes,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<_JsonMrpackEnv>,
+    pub downloads: Vec<String>,
+    #[serde(default)]
+    pub file_size: u64,
+}
+
+/// `modrinth.index.json` of the `.mrpack` modpack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMrpackIndex {
+    pub format_version: u32,
+    pub game: String,
+    pub version_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<_JsonMrpackFile>,
+    /// `minecraft` and the loader, e.g. `fabric-loader`, with their versions
+    pub dependencies: BTreeMap<String, String>,
+}
@@ -0,0 +1,22 @@
+use serde::Deserialize;
+
+/// `mmc-pack.json` of the MultiMC / Prism instance
+#[derive(Debug, Deserialize)]
+pub struct JsonMultiMcPack {
+    #[serde(default)]
+    pub components: Vec<_JsonMultiMcComponent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct _JsonMultiMcComponent {
+    pub uid: String,
+    pub version: Option<String>,
+    pub cached_version: Option<String>,
+}
+
+impl _JsonMultiMcComponent {
+    pub fn get_version(&self) -> Option<&str> {
+        self.version.as_deref().or(self.cached_version.as_deref())
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// `launcher_profiles.json` of the official launcher
+///
+/// Unknown fields are kept, so the file can be written back without losing them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JsonLauncherProfiles {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, _JsonLauncherProfile>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct _JsonLauncherProfile {
+    #[serde(default)]
+    pub name: String,
+    /// `custom`, `latest-release` or `latest-snapshot`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub profile_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_version_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java_args: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<_JsonProfileResolution>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct _JsonProfileResolution {
+    pub width: u32,
+    pub height: u32,
+}
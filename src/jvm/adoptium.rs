@@ -0,0 +1,325 @@
+use std::path::{Path, PathBuf};
+
+use futures::future::BoxFuture;
+
+use super::JvmProvider;
+use crate::{
+    helpers,
+    internal_types::{shared::_JsonJavaVersion, shared_jvm::JsonAdoptiumAsset},
+    types::{Error, Launcher, Platform},
+    utils,
+};
+
+const ADOPTIUM_API_URL: &str = "https://api.adoptium.net";
+
+/// Temurin runtimes from an Adoptium compatible API
+///
+/// Releases are requested from `<base_url>/v3/assets/latest/<major>/hotspot`,
+/// so a local mirror with the same layout can be used as well
+pub struct AdoptiumProvider {
+    pub base_url: String,
+    /// `jre` or `jdk`
+    pub image_type: String,
+}
+
+impl Default for AdoptiumProvider {
+    fn default() -> Self {
+        AdoptiumProvider {
+            base_url: ADOPTIUM_API_URL.to_string(),
+            image_type: "jre".to_string(),
+        }
+    }
+}
+
+/// Get os and arch names used by the Adoptium API
+fn get_adoptium_platform<'a>() -> Option<(&'a str, &'a str)> {
+    let os = match utils::get_platform() {
+        Platform::Linux => "linux",
+        Platform::Windows => "windows",
+        Platform::Darwin => "mac",
+    };
+    let arch = match utils::get_arch() {
+        "x86_64" => "x64",
+        "aarch64" => "aarch64",
+        "x86" => "x86",
+        "arm64" => "arm",
+        _ => return None,
+    };
+    Some((os, arch))
+}
+
+/// Move the single top level directory of the archive to `dest`
+async fn move_archive_root(from: &Path, dest: &Path) -> Result<(), Error> {
+    let mut entries = std::fs::read_dir(from)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<PathBuf>>();
+
+    let root = match entries.len() {
+        1 if entries[0].is_dir() => entries.remove(0),
+        _ => from.to_path_buf(),
+    };
+
+    tokio::fs::rename(root, dest).await?;
+    Ok(())
+}
+
+impl AdoptiumProvider {
+    pub fn new(base_url: &str) -> Self {
+        AdoptiumProvider {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Get the latest GA release, `None` if there are no releases for the platform
+    async fn get_latest_asset(
+        &self,
+        major_version: u16,
+        os: &str,
+        arch: &str,
+    ) -> Result<Option<JsonAdoptiumAsset>, Error> {
+        let url = format!(
+            "{}/v3/assets/latest/{major_version}/hotspot?os={os}&architecture={arch}&image_type={}&vendor=eclipse",
+            self.base_url, self.image_type
+        );
+        let data = match helpers::http::get(&url, None).await {
+            Ok(data) => data,
+            Err(Error::reqwest(err)) if err.status() == Some(reqwest::StatusCode::NOT_FOUND) => {
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        };
+        let assets: Vec<JsonAdoptiumAsset> = serde_json::from_slice(&data)?;
+        Ok(assets.into_iter().find(|i| {
+            i.binary.os == os
+                && i.binary.architecture == arch
+                && i.binary.image_type == self.image_type
+        }))
+    }
+
+    pub async fn install_runtime(
+        &self,
+        launcher: &Launcher,
+        major_version: u16,
+    ) -> Result<Option<PathBuf>, Error> {
+        let Some((os, arch)) = get_adoptium_platform() else {
+            log::warn!("Adoptium runtimes are not available for this platform");
+            return Ok(None);
+        };
+
+        // Повторяет раскладку runtime/<component>/<platform>/<component>,
+        // чтобы установленный runtime находился при поиске java
+        let name = format!("temurin-{major_version}");
+        let root = launcher
+            .path
+            .join("runtime")
+            .join(&name)
+            .join(format!("{os}-{arch}"));
+        let home = root.join(&name);
+        if home.exists() {
+            log::info!("Jvm already installed");
+            return Ok(Some(home));
+        }
+
+        log::info!("Resolving temurin {major_version} for {os}-{arch}...");
+        let Some(asset) = self.get_latest_asset(major_version, os, arch).await? else {
+            log::warn!("Temurin {major_version} not found for {os}-{arch}");
+            return Ok(None);
+        };
+        let package = asset.binary.package;
+
+        log::info!("Downloading {}...", asset.release_name);
+        let archive = helpers::http::get(&package.link, None).await?;
+        if !utils::get_sha256(&archive).eq_ignore_ascii_case(&package.checksum) {
+            return Err(Error::Jvm(format!("Checksum mismatch of {}", package.name)));
+        }
+
+        tokio::fs::create_dir_all(&root).await?;
+        let archive_path = root.join(format!("{name}.download"));
+        tokio::fs::write(&archive_path, &archive).await?;
+
+        // Распаковка во временную директорию, чтобы прерванная установка
+        // не выглядела как готовый runtime
+        let tmp_path = root.join(format!("{name}.tmp"));
+        if tmp_path.exists() {
+            tokio::fs::remove_dir_all(&tmp_path).await?;
+        }
+        tokio::fs::create_dir_all(&tmp_path).await?;
+
+        log::info!("Unpacking temurin {major_version}...");
+        if archive.starts_with(b"PK") {
+            utils::unzip(&archive_path, &tmp_path).await?;
+        } else {
+            utils::untar_gz(&archive_path, &tmp_path).await?;
+        }
+
+        move_archive_root(&tmp_path, &home).await?;
+        if tmp_path.exists() {
+            tokio::fs::remove_dir_all(&tmp_path).await?;
+        }
+        tokio::fs::remove_file(&archive_path).await?;
+
+        log::info!("Jvm installed!");
+        Ok(Some(home))
+    }
+}
+
+impl JvmProvider for AdoptiumProvider {
+    fn name(&self) -> &str {
+        "adoptium"
+    }
+
+    fn install<'a>(
+        &'a self,
+        launcher: &'a Launcher,
+        java_version: &'a _JsonJavaVersion,
+    ) -> BoxFuture<'a, Result<Option<PathBuf>, Error>> {
+        Box::pin(self.install_runtime(launcher, java_version.major_version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use serde_json::json;
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+    use crate::helpers::testing::{MockServer, TempLauncher};
+
+    const ASSETS_PATH: &str = "/v3/assets/latest/17/hotspot";
+
+    fn get_zip() -> Vec<u8> {
+        let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("jdk-17.0.9+9-jre/bin/java", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"java").unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn get_tar_gz() -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o755);
+        header.set_cksum();
+        tar.append_data(&mut header, "jdk-17.0.9+9-jre/bin/java", &b"java"[..])
+            .unwrap();
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// Fixture of the assets API answer for the current platform
+    fn route_release(server: &MockServer, archive: &[u8], checksum: &str) {
+        let (os, arch) = get_adoptium_platform().unwrap();
+        server.route("/archive", 200, archive.to_vec());
+        server.route_json(
+            ASSETS_PATH,
+            json!([
+                {
+                    "binary": {
+                        "os": os,
+                        "architecture": arch,
+                        "image_type": "jdk",
+                        "package": { "name": "jdk.tar.gz", "link": "unused", "checksum": "0" },
+                    },
+                    "release_name": "jdk-17.0.9+9",
+                },
+                {
+                    "binary": {
+                        "os": os,
+                        "architecture": arch,
+                        "image_type": "jre",
+                        "package": {
+                            "name": "jre.archive",
+                            "link": format!("{}/archive", server.url),
+                            "checksum": checksum,
+                        },
+                    },
+                    "release_name": "jdk-17.0.9+9",
+                },
+            ]),
+        );
+    }
+
+    async fn assert_installed(archive: Vec<u8>) {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        route_release(&server, &archive, &utils::get_sha256(&archive));
+
+        let provider = AdoptiumProvider::new(&format!("{}/", server.url));
+        let home = provider
+            .install_runtime(&temp.launcher, 17)
+            .await
+            .unwrap()
+            .unwrap();
+        let (os, arch) = get_adoptium_platform().unwrap();
+        let root = temp
+            .path()
+            .join("runtime")
+            .join("temurin-17")
+            .join(format!("{os}-{arch}"));
+        assert_eq!(home, root.join("temurin-17"));
+        assert_eq!(
+            std::fs::read(home.join("bin").join("java")).unwrap(),
+            b"java"
+        );
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 1);
+
+        let request = &server.requests()[0];
+        assert!(request.target.starts_with(ASSETS_PATH));
+        assert!(
+            request
+                .target
+                .contains(&format!("os={os}&architecture={arch}"))
+        );
+        assert!(request.target.contains("image_type=jre"));
+    }
+
+    #[tokio::test]
+    async fn zip_runtime_is_installed() {
+        assert_installed(get_zip()).await;
+    }
+
+    #[tokio::test]
+    async fn tar_gz_runtime_is_installed() {
+        assert_installed(get_tar_gz()).await;
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch_is_rejected() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        route_release(&server, &get_zip(), &utils::get_sha256(b"other"));
+
+        let provider = AdoptiumProvider::new(&server.url);
+        let result = provider.install_runtime(&temp.launcher, 17).await;
+        assert!(matches!(result, Err(Error::Jvm(_))));
+        assert!(!temp.path().join("runtime").exists());
+    }
+
+    #[tokio::test]
+    async fn missing_release_is_none() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let provider = AdoptiumProvider::new(&server.url);
+        assert!(
+            provider
+                .install_runtime(&temp.launcher, 17)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        server.route_json(ASSETS_PATH, json!([]));
+        assert!(
+            provider
+                .install_runtime(&temp.launcher, 17)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::{
+    collections::HashSet,
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use tokio::process::Command;
+
+use super::mojang;
+use crate::{
+    types::{Error, Launcher, Platform},
+    utils,
+};
+
+/// Java installation found on the system
+#[derive(Debug, Clone)]
+pub struct JavaInstallation {
+    /// Path to the `java` executable
+    pub path: PathBuf,
+    pub version: String,
+    pub major_version: u16,
+    pub vendor: String,
+    pub arch: String,
+}
+
+impl JavaInstallation {
+    /// Check if installation can run the current platform natives
+    pub fn is_native_arch(&self) -> bool {
+        normalize_arch(&self.arch) == utils::get_arch()
+    }
+}
+
+fn get_java_binary_name<'a>() -> &'a str {
+    match utils::get_platform() {
+        Platform::Windows => "java.exe",
+        _ => "java",
+    }
+}
+
+fn normalize_arch(arch: &str) -> &str {
+    match arch {
+        "amd64" | "x86_64" => "x86_64",
+        "aarch64" | "arm64" => "aarch64",
+        "x86" | "i386" | "i686" => "x86",
+        v => v,
+    }
+}
+
+/// Parse major version from `java.specification.version`, e.g. `1.8` -> 8, `21` -> 21
+fn parse_major_version(version: &str) -> Option<u16> {
+    let mut parts = version.split('.');
+    match parts.next()? {
+        "1" => parts.next()?.parse().ok(),
+        major => major.parse().ok(),
+    }
+}
+
+/// Get path to `java` inside of java home directory
+pub fn get_java_from_home(home: &Path) -> Option<PathBuf> {
+    let name = get_java_binary_name();
+    [
+        home.join("bin").join(name),
+        home.join("Contents").join("Home").join("bin").join(name),
+        home.join("jre.bundle")
+            .join("Contents")
+            .join("Home")
+            .join("bin")
+            .join(name),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+fn get_subdirs(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Collect paths to `java` executables, in order of preference
+async fn get_java_candidates(launcher: &Launcher) -> Vec<PathBuf> {
+    collect_java_candidates(launcher, env::var_os("JAVA_HOME"), env::var_os("PATH")).await
+}
+
+/// Collect `java` from the launcher runtimes, `java_home`, `path` and `/usr/lib/jvm`
+///
+/// Mojang runtimes are skipped unless [`mojang::verify_jvm_runtime`] accepts their
+/// markers. `temurin-*` runtimes have no markers and are exempt from the check:
+/// they are unpacked to `<component>.tmp` and renamed to the java home only when
+/// complete, so only the `<platform>/<component>` home is looked at
+async fn collect_java_candidates(
+    launcher: &Launcher,
+    java_home: Option<OsString>,
+    path: Option<OsString>,
+) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    // runtime/<component>/<platform>/<component>
+    for component_dir in get_subdirs(&launcher.path.join("runtime")) {
+        let component = component_dir
+            .file_name()
+            .map(|i| i.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // Недоустановленный runtime Mojang остается провайдеру для починки
+        if !component.starts_with("temurin-")
+            && !mojang::verify_jvm_runtime(launcher, &component)
+                .await
+                .unwrap_or(false)
+        {
+            log::warn!("Skipping runtime {component}, it is not fully installed");
+            continue;
+        }
+        // Прерванная распаковка остается рядом в <component>.tmp
+        for platform in get_subdirs(&component_dir) {
+            candidates.extend(get_java_from_home(&platform.join(&component)));
+        }
+    }
+
+    if let Some(home) = java_home {
+        candidates.extend(get_java_from_home(Path::new(&home)));
+    }
+
+    if let Some(path) = path {
+        let name = get_java_binary_name();
+        for dir in env::split_paths(&path) {
+            let java = dir.join(name);
+            if java.is_file() {
+                candidates.push(java);
+            }
+        }
+    }
+
+    if matches!(utils::get_platform(), Platform::Linux) {
+        for home in get_subdirs(Path::new("/usr/lib/jvm")) {
+            candidates.extend(get_java_from_home(&home));
+        }
+    }
+
+    // Один и тот же java часто доступен по нескольким путям (PATH, JAVA_HOME, симлинки)
+    let mut seen = HashSet::new();
+    candidates.retain(|path| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())));
+    candidates
+}
+
+/// Run `java -XshowSettings:properties -version` and read its properties
+pub async fn probe_java(path: &Path) -> Result<JavaInstallation, Error> {
+    log::debug!("Probing java {path:?}");
+    let output = Command::new(path)
+        .arg("-XshowSettings:properties")
+        .arg("-version")
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+
+    // Свойства печатаются в stderr
+    parse_java_properties(path, &String::from_utf8_lossy(&output.stderr))
+}
+
+/// Read installation of java at `path` from `-XshowSettings:properties` output
+fn parse_java_properties(path: &Path, output: &str) -> Result<JavaInstallation, Error> {
+    let mut spec_version = None;
+    let mut version = None;
+    let mut vendor = None;
+    let mut arch = None;
+
+    for line in output.lines() {
+        let Some((key, value)) = line.trim().split_once(" = ") else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key {
+            "java.specification.version" => spec_version = Some(value),
+            "java.version" => version = Some(value),
+            "java.vendor" => vendor = Some(value),
+            "os.arch" => arch = Some(value),
+            _ => {}
+        }
+    }
+
+    let major_version = spec_version
+        .as_deref()
+        .and_then(parse_major_version)
+        .ok_or_else(|| Error::Jvm(format!("Failed to read java version of {path:?}")))?;
+
+    Ok(JavaInstallation {
+        path: path.to_path_buf(),
+        version: version.unwrap_or_default(),
+        major_version,
+        vendor: vendor.unwrap_or_default(),
+        arch: arch.unwrap_or_default(),
+    })
+}
+
+/// Find all java installations from `JAVA_HOME`, `PATH`, `/usr/lib/jvm`
+/// and the launcher `runtime/` directory
+pub async fn find_java_installations(launcher: &Launcher) -> Vec<JavaInstallation> {
+    let mut installations = Vec::new();
+
+    for path in get_java_candidates(launcher).await {
+        match probe_java(&path).await {
+            Ok(java) => installations.push(java),
+            Err(err) => log::warn!("Skipping java {path:?}: {err:?}"),
+        }
+    }
+
+    installations
+}
+
+/// Find installed java with exactly `major_version` for the current architecture
+pub async fn find_compatible_java(
+    launcher: &Launcher,
+    major_version: u16,
+) -> Option<JavaInstallation> {
+    find_java_installations(launcher)
+        .await
+        .into_iter()
+        .find(|java| java.major_version == major_version && java.is_native_arch())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::TempLauncher;
+
+    /// Create fake java home with the `java` executable
+    fn create_java_home(home: &Path) -> PathBuf {
+        let java = home.join("bin").join(get_java_binary_name());
+        std::fs::create_dir_all(java.parent().unwrap()).unwrap();
+        std::fs::write(&java, "").unwrap();
+        java
+    }
+
+    #[test]
+    fn java_8_properties_are_parsed() {
+        let output = "Property settings:
+    java.specification.version = 1.8
+    java.vendor = Eclipse Adoptium
+    java.version = 1.8.0_402
+    line.separator = \\n
+    os.arch = amd64
+
+openjdk version \"1.8.0_402\"
+";
+        let java = parse_java_properties(Path::new("java"), output).unwrap();
+        assert_eq!(java.major_version, 8);
+        assert_eq!(java.version, "1.8.0_402");
+        assert_eq!(java.vendor, "Eclipse Adoptium");
+        assert_eq!(java.arch, "amd64");
+        assert_eq!(normalize_arch(&java.arch), "x86_64");
+    }
+
+    #[test]
+    fn modern_properties_are_parsed() {
+        let output = "    java.specification.version = 21\r
+    java.version = 21.0.2\r
+    os.arch = aarch64\r
+";
+        let java = parse_java_properties(Path::new("java"), output).unwrap();
+        assert_eq!(java.major_version, 21);
+        assert_eq!(java.version, "21.0.2");
+        assert_eq!(java.vendor, "");
+        assert_eq!(normalize_arch(&java.arch), "aarch64");
+    }
+
+    #[test]
+    fn missing_version_is_error() {
+        let output = "Error: Could not create the Java Virtual Machine.\n";
+        assert!(matches!(
+            parse_java_properties(Path::new("java"), output),
+            Err(Error::Jvm(_))
+        ));
+        assert_eq!(parse_major_version("1.8"), Some(8));
+        assert_eq!(parse_major_version("17"), Some(17));
+        assert_eq!(parse_major_version("1"), None);
+    }
+
+    #[tokio::test]
+    async fn candidates_are_ordered_and_filtered() {
+        let temp = TempLauncher::new();
+        let runtime = temp.path().join("runtime");
+        let mut expected = Vec::new();
+
+        if let Some(platform) = mojang::get_jvm_platform() {
+            let root = runtime.join("java-runtime-delta").join(platform);
+            expected.push(create_java_home(&root.join("java-runtime-delta")));
+            std::fs::write(root.join(".version"), "17.0.8").unwrap();
+            std::fs::write(root.join("java-runtime-delta.sha1"), "").unwrap();
+
+            // Без маркеров runtime считается недоустановленным
+            let root = runtime.join("java-runtime-gamma").join(platform);
+            create_java_home(&root.join("java-runtime-gamma"));
+        }
+
+        let root = runtime.join("temurin-17").join("linux-x64");
+        expected.push(create_java_home(&root.join("temurin-17")));
+        // Прерванная распаковка
+        create_java_home(&root.join("temurin-17.tmp"));
+        create_java_home(
+            &runtime
+                .join("temurin-21")
+                .join("linux-x64")
+                .join("temurin-21.tmp"),
+        );
+
+        let java_home = temp.path().join("jdk");
+        expected.push(create_java_home(&java_home));
+        // PATH повторяет JAVA_HOME, дубликат отбрасывается
+        expected.push(create_java_home(&temp.path()));
+        let path = env::join_paths([temp.path().join("bin"), java_home.join("bin")]).unwrap();
+
+        let candidates: Vec<PathBuf> =
+            collect_java_candidates(&temp.launcher, Some(java_home.into()), Some(path))
+                .await
+                .into_iter()
+                .filter(|i| i.starts_with(temp.path()))
+                .collect();
+        assert_eq!(candidates, expected);
+    }
+}
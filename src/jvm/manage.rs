@@ -0,0 +1,329 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use super::{adoptium::AdoptiumProvider, mojang};
+use crate::{
+    instances::{self, JavaChoice},
+    internal_types::shared::_JsonJavaVersion,
+    types::{Error, Launcher},
+};
+
+/// Runtime component installed under `runtime/`
+#[derive(Debug, Clone)]
+pub struct InstalledRuntime {
+    /// Component name, e.g. `java-runtime-delta` or `temurin-21`
+    pub component: String,
+    pub platform: String,
+    /// Version from the `.version` marker, runtimes without it are incomplete
+    /// or installed by other providers
+    pub version: Option<String>,
+    /// Java home of the runtime
+    pub path: PathBuf,
+    /// Size on disk in bytes
+    pub size: u64,
+}
+
+fn get_dir_size(path: &Path) -> Result<u64, Error> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        // Ссылки не разыменовываются, чтобы не считать файлы дважды
+        let metadata = entry.path().symlink_metadata()?;
+        if metadata.is_dir() {
+            size += get_dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+fn read_dir_names(path: &Path) -> Result<Vec<String>, Error> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// List runtime components installed under `runtime/`
+pub async fn list_runtimes(launcher: &Launcher) -> Result<Vec<InstalledRuntime>, Error> {
+    let runtime_dir = launcher.path.join("runtime");
+    let mut runtimes = Vec::new();
+
+    // runtime/<component>/<platform>/<component>
+    for component in read_dir_names(&runtime_dir)? {
+        let component_dir = runtime_dir.join(&component);
+        for platform in read_dir_names(&component_dir)? {
+            let root = component_dir.join(&platform);
+            let path = root.join(&component);
+            if !path.exists() {
+                continue;
+            }
+
+            let version = tokio::fs::read_to_string(root.join(".version"))
+                .await
+                .ok()
+                .map(|version| version.trim().to_string());
+
+            runtimes.push(InstalledRuntime {
+                size: get_dir_size(&path)?,
+                component: component.clone(),
+                platform,
+                version,
+                path,
+            });
+        }
+    }
+
+    Ok(runtimes)
+}
+
+/// Install runtime `component` without any version json and return its java home
+///
+/// `temurin-<major>` components are installed with the default [`AdoptiumProvider`],
+/// any other name is looked up in the Mojang manifest
+pub async fn install_runtime(launcher: &Launcher, component: &str) -> Result<PathBuf, Error> {
+    log::info!("Installing runtime \"{component}\"...");
+    let home = match component.strip_prefix("temurin-") {
+        Some(major) => {
+            let major = major
+                .parse()
+                .map_err(|_| Error::Jvm(format!("Invalid temurin runtime \"{component}\"")))?;
+            AdoptiumProvider::default()
+                .install_runtime(launcher, major)
+                .await?
+        }
+        None => mojang::install_jvm_component(launcher, component).await?,
+    };
+
+    home.ok_or_else(|| Error::Jvm(format!("Jvm \"{component}\" not found")))
+}
+
+/// Remove runtime `component` for all platforms
+pub async fn remove_runtime(launcher: &Launcher, component: &str) -> Result<(), Error> {
+    if component.is_empty() || component.contains(['/', '\\']) || component == ".." {
+        return Err(Error::Jvm(format!("Invalid runtime name \"{component}\"")));
+    }
+    let path = launcher.path.join("runtime").join(component);
+
+    log::info!("Removing runtime \"{component}\"...");
+    tokio::fs::remove_dir_all(path).await?;
+    Ok(())
+}
+
+/// Get `javaVersion` of the installed version, inheriting versions are followed
+/// to their parents
+///
+/// Only installed jsons are read. `None` if a parent is not installed, such version
+/// gets its runtime again when it is installed
+fn get_java_version(
+    versions_dir: &Path,
+    id: &str,
+    visited: &mut HashSet<String>,
+) -> Result<Option<_JsonJavaVersion>, Error> {
+    if !visited.insert(id.to_string()) {
+        return Err(Error::Jvm(format!("Version {id} inherits from itself")));
+    }
+    let path = versions_dir.join(id).join(format!("{id}.json"));
+    let value: Value = match std::fs::read(&path) {
+        Ok(data) => serde_json::from_slice(&data)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    if let Some(java_version) = value.get("javaVersion") {
+        return Ok(Some(serde_json::from_value(java_version.clone())?));
+    }
+    match value["inheritsFrom"].as_str() {
+        Some(parent_id) => get_java_version(versions_dir, parent_id, visited),
+        None => Err(Error::Jvm(format!("Version {id} has no javaVersion"))),
+    }
+}
+
+/// Collect runtime components referenced by the versions under `versions/`
+/// and pinned by the instances
+///
+/// Inheriting versions are merged with their parents to get `javaVersion`.
+/// Any version which can't be read is an error, so its runtime is never
+/// treated as unused
+pub async fn get_used_runtimes(launcher: &Launcher) -> Result<HashSet<String>, Error> {
+    let versions_dir = launcher.path.join("versions");
+    let mut used = HashSet::new();
+
+    for id in read_dir_names(&versions_dir)? {
+        if !versions_dir.join(&id).join(format!("{id}.json")).exists() {
+            continue;
+        }
+        let java_version = get_java_version(&versions_dir, &id, &mut HashSet::new())
+            .map_err(|err| Error::Jvm(format!("Failed to read version {id}: {err:?}")))?;
+        let Some(java_version) = java_version else {
+            log::warn!("Parent of version {id} is not installed, skipping");
+            continue;
+        };
+
+        used.insert(java_version.component);
+        // Runtime от другого провайдера с той же мажорной версией
+        used.insert(format!("temurin-{}", java_version.major_version));
+    }
+
+    for instance in instances::list_instances(launcher)? {
+        if let JavaChoice::Runtime { component } = &instance.java {
+            used.insert(component.clone());
+        }
+    }
+
+    Ok(used)
+}
+
+/// Remove runtimes not referenced by any installed version or instance
+///
+/// Returns names of the removed components
+pub async fn remove_unused_runtimes(launcher: &Launcher) -> Result<Vec<String>, Error> {
+    let used = get_used_runtimes(launcher).await?;
+    let mut removed = Vec::new();
+
+    for component in read_dir_names(&launcher.path.join("runtime"))? {
+        if used.contains(&component) {
+            continue;
+        }
+        remove_runtime(launcher, &component).await?;
+        removed.push(component);
+    }
+
+    log::info!("Removed {} unused runtimes", removed.len());
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::helpers::testing::TempLauncher;
+
+    fn add_runtime(temp: &TempLauncher, component: &str) {
+        let home = temp
+            .path()
+            .join("runtime")
+            .join(component)
+            .join("linux")
+            .join(component);
+        std::fs::create_dir_all(home.join("bin")).unwrap();
+    }
+
+    fn add_version(temp: &TempLauncher, id: &str, value: &Value) {
+        let dir = temp.path().join("versions").join(id);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{id}.json")), value.to_string()).unwrap();
+    }
+
+    fn get_runtimes(temp: &TempLauncher) -> Vec<String> {
+        read_dir_names(&temp.path().join("runtime")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn used_runtimes_are_kept() {
+        let temp = TempLauncher::new();
+        for component in [
+            "java-runtime-delta",
+            "java-runtime-gamma",
+            "jre-legacy",
+            "temurin-21",
+        ] {
+            add_runtime(&temp, component);
+        }
+        add_version(
+            &temp,
+            "1.21.8",
+            &json!({ "id": "1.21.8", "javaVersion": { "component": "java-runtime-delta", "majorVersion": 21 } }),
+        );
+        add_version(
+            &temp,
+            "fabric-loader-0.17.2-1.21.8",
+            &json!({ "id": "fabric-loader-0.17.2-1.21.8", "inheritsFrom": "1.21.8" }),
+        );
+        // Родитель не установлен, версия runtime не использует
+        add_version(
+            &temp,
+            "forge",
+            &json!({ "id": "forge", "inheritsFrom": "1.16.5" }),
+        );
+
+        let mut instance = instances::create_instance(&temp.launcher, "pinned", "1.21.8").unwrap();
+        instance.java = JavaChoice::Runtime {
+            component: "java-runtime-gamma".to_string(),
+        };
+        instance.save().unwrap();
+
+        let used = get_used_runtimes(&temp.launcher).await.unwrap();
+        assert!(used.contains("java-runtime-delta"));
+        assert!(used.contains("temurin-21"));
+        assert!(used.contains("java-runtime-gamma"));
+
+        let removed = remove_unused_runtimes(&temp.launcher).await.unwrap();
+        assert_eq!(removed, ["jre-legacy"]);
+        assert_eq!(
+            get_runtimes(&temp),
+            ["java-runtime-delta", "java-runtime-gamma", "temurin-21"]
+        );
+    }
+
+    #[tokio::test]
+    async fn inheriting_version_is_read_without_network() {
+        let temp = TempLauncher::new();
+        add_runtime(&temp, "java-runtime-delta");
+        add_version(
+            &temp,
+            "loader",
+            &json!({
+                "id": "loader",
+                "inheritsFrom": "1.21.8",
+                "javaVersion": { "component": "java-runtime-delta", "majorVersion": 21 },
+            }),
+        );
+        assert!(
+            remove_unused_runtimes(&temp.launcher)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!temp.path().join("versions").join("1.21.8").exists());
+    }
+
+    #[tokio::test]
+    async fn unreadable_version_aborts_removal() {
+        let temp = TempLauncher::new();
+        add_runtime(&temp, "jre-legacy");
+        add_version(&temp, "1.21.8", &json!({ "id": "1.21.8" }));
+        let dir = temp.path().join("versions").join("broken");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        assert!(matches!(
+            remove_unused_runtimes(&temp.launcher).await,
+            Err(Error::Jvm(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+        // Версия без javaVersion тоже не считается неиспользующей runtime
+        assert!(remove_unused_runtimes(&temp.launcher).await.is_err());
+
+        add_version(&temp, "a", &json!({ "id": "a", "inheritsFrom": "b" }));
+        add_version(&temp, "b", &json!({ "id": "b", "inheritsFrom": "a" }));
+        std::fs::remove_dir_all(temp.path().join("versions").join("1.21.8")).unwrap();
+        assert!(remove_unused_runtimes(&temp.launcher).await.is_err());
+        assert_eq!(get_runtimes(&temp), ["jre-legacy"]);
+    }
+}
@@ -0,0 +1,84 @@
+pub mod adoptium;
+pub mod discovery;
+pub mod manage;
+pub mod mojang;
+
+use std::path::PathBuf;
+
+use futures::future::BoxFuture;
+
+use adoptium::AdoptiumProvider;
+use discovery::JavaInstallation;
+use mojang::MojangProvider;
+
+use crate::{
+    internal_types::shared::{_JsonJavaVersion, VersionJson},
+    types::{Error, Launcher},
+};
+
+/// Source of java runtimes
+pub trait JvmProvider: Send + Sync {
+    /// Provider name for logs
+    fn name(&self) -> &str;
+
+    /// Install runtime for `java_version` and return its java home
+    ///
+    /// `None` means the provider has no runtime for the current platform
+    fn install<'a>(
+        &'a self,
+        launcher: &'a Launcher,
+        java_version: &'a _JsonJavaVersion,
+    ) -> BoxFuture<'a, Result<Option<PathBuf>, Error>>;
+}
+
+/// Mojang runtimes with Adoptium as the fallback
+pub fn get_default_providers() -> Vec<Box<dyn JvmProvider>> {
+    vec![
+        Box::new(MojangProvider),
+        Box::new(AdoptiumProvider::default()),
+    ]
+}
+
+/// Get java runtime for the version using the default providers
+pub async fn get_java(launcher: &Launcher, info: &VersionJson) -> Result<JavaInstallation, Error> {
+    get_java_with_providers(launcher, info, &get_default_providers()).await
+}
+
+/// Get java runtime for the version
+///
+/// Installed java with matching major version is preferred,
+/// otherwise runtime is installed by the first provider that has it
+pub async fn get_java_with_providers(
+    launcher: &Launcher,
+    info: &VersionJson,
+    providers: &[Box<dyn JvmProvider>],
+) -> Result<JavaInstallation, Error> {
+    let major_version = info.java_version.major_version;
+    if let Some(java) = discovery::find_compatible_java(launcher, major_version).await {
+        log::info!(
+            "Using java {} ({}) from {:?}",
+            java.version,
+            java.vendor,
+            java.path
+        );
+        return Ok(java);
+    }
+
+    for provider in providers {
+        log::info!(
+            "Java {major_version} not found, trying \"{}\" provider",
+            provider.name()
+        );
+        let Some(home) = provider.install(launcher, &info.java_version).await? else {
+            continue;
+        };
+
+        let path = discovery::get_java_from_home(&home)
+            .ok_or_else(|| Error::Jvm(format!("Java executable not found in {home:?}")))?;
+        return discovery::probe_java(&path).await;
+    }
+
+    Err(Error::Jvm(format!(
+        "No provider has java {major_version} for this platform"
+    )))
+}
@@ -0,0 +1,450 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use futures::future::BoxFuture;
+
+use super::JvmProvider;
+use crate::{
+    helpers,
+    install::download_file,
+    internal_types::{
+        shared::{_JsonJavaVersion, VersionJson},
+        shared_jvm::{_JavaRuntimesManifestItem, JavaRuntimeFiles, JavaRuntimesManifest},
+    },
+    types::{Error, Launcher, Platform},
+    utils,
+};
+
+const JVM_MANIFEST_URL: &str = "https://launchermeta.mojang.com/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+
+/// Get platform name used in the runtimes manifest
+///
+/// `None` is returned for platforms without Mojang runtimes, e.g. Linux on ARM
+pub(crate) fn get_jvm_platform<'a>() -> Option<&'a str> {
+    let platform = utils::get_platform();
+    let arch = utils::get_arch();
+
+    let name = match (platform, arch) {
+        (Platform::Windows, "x86") => "windows-x86",
+        (Platform::Windows, "x86_64") => "windows-x64",
+        (Platform::Windows, "aarch64") => "windows-arm64",
+        (Platform::Linux, "x86") => "linux-i386",
+        (Platform::Linux, "x86_64") => "linux",
+        (Platform::Darwin, "aarch64") => "mac-os-arm64",
+        (Platform::Darwin, "x86_64") => "mac-os",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Resolve link `target` relative to the directory of `key`
+///
+/// Both paths are relative to the runtime root, `None` is returned
+/// if the target escapes it
+fn resolve_link_target(key: &str, target: &str) -> Option<String> {
+    let mut parts: Vec<&str> = key.split('/').collect();
+    parts.pop();
+
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+
+    Some(parts.join("/"))
+}
+
+/// Order links so that every link comes after the link it points to
+fn sort_links<'a>(
+    links: &HashMap<&'a str, &'a str>,
+    manifest: &JavaRuntimeFiles,
+) -> Result<Vec<&'a str>, Error> {
+    fn visit<'a>(
+        key: &'a str,
+        links: &HashMap<&'a str, &'a str>,
+        manifest: &JavaRuntimeFiles,
+        visiting: &mut HashSet<&'a str>,
+        sorted: &mut Vec<&'a str>,
+    ) -> Result<(), Error> {
+        if sorted.contains(&key) {
+            return Ok(());
+        }
+        if !visiting.insert(key) {
+            return Err(Error::Jvm(format!("Link cycle detected at \"{key}\"")));
+        }
+
+        let target = resolve_link_target(key, links[key])
+            .ok_or_else(|| Error::Jvm(format!("Link \"{key}\" points outside of runtime")))?;
+
+        if let Some((&target_key, _)) = links.get_key_value(target.as_str()) {
+            visit(target_key, links, manifest, visiting, sorted)?;
+        } else if !manifest.files.contains_key(&target) {
+            return Err(Error::Jvm(format!(
+                "Link \"{key}\" points to missing target \"{target}\""
+            )));
+        }
+
+        visiting.remove(key);
+        sorted.push(key);
+        Ok(())
+    }
+
+    let mut keys: Vec<&str> = links.keys().copied().collect();
+    keys.sort();
+
+    let mut visiting = HashSet::new();
+    let mut sorted = Vec::with_capacity(keys.len());
+    for key in keys {
+        visit(key, links, manifest, &mut visiting, &mut sorted)?;
+    }
+
+    Ok(sorted)
+}
+
+async fn create_link(
+    base_path: &Path,
+    key: &str,
+    target: &str,
+    manifest: &JavaRuntimeFiles,
+) -> Result<(), Error> {
+    let current_path = base_path.join(key);
+    if tokio::fs::symlink_metadata(&current_path).await.is_ok() {
+        return Ok(());
+    }
+
+    let parent = current_path.parent().unwrap_or(base_path);
+    tokio::fs::create_dir_all(parent).await?;
+
+    #[cfg(unix)]
+    {
+        log::info!("Creating link: {current_path:?} -> {target}");
+        match tokio::fs::symlink(target, &current_path).await {
+            Ok(()) => return Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+                log::warn!("Symlinks are not permitted ({err}), falling back to copy");
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    let resolved = resolve_link_target(key, target)
+        .ok_or_else(|| Error::Jvm(format!("Link \"{key}\" points outside of runtime")))?;
+    let copy_from = base_path.join(&resolved);
+    log::info!("Copying link target: {copy_from:?} -> {current_path:?}");
+
+    if manifest
+        .files
+        .get(&resolved)
+        .is_some_and(|item| item.item_type == "directory")
+    {
+        return Err(Error::Jvm(format!(
+            "Link \"{key}\" points to directory and can't be copied"
+        )));
+    }
+
+    tokio::fs::copy(&copy_from, &current_path).await?;
+    Ok(())
+}
+
+async fn get_jvm_runtimes(
+    manifest_data: &JavaRuntimesManifest,
+    platform_str: &str,
+) -> Result<Vec<String>, Error> {
+    let mut jvm_list: Vec<String> = Vec::new();
+
+    let platform_jvms = manifest_data.platforms.get(platform_str);
+    if let Some(list) = platform_jvms {
+        for key in list.keys() {
+            jvm_list.push(key.to_string());
+        }
+    }
+
+    Ok(jvm_list)
+}
+
+/// Download runtime manifest of `component` for the current platform
+///
+/// `None` is returned if Mojang has no such runtime for the platform
+async fn get_component_manifest(
+    component: &str,
+    client: &reqwest::Client,
+) -> Result<Option<(_JavaRuntimesManifestItem, JavaRuntimeFiles)>, Error> {
+    let Some(platform_str) = get_jvm_platform() else {
+        log::warn!("Mojang runtimes are not available for this platform");
+        return Ok(None);
+    };
+    log::info!("Getting jvm runtimes for {}", &platform_str);
+
+    let raw_manifest_data = helpers::http::get(JVM_MANIFEST_URL, Some(client)).await?;
+    let manifest_data = serde_json::from_slice::<JavaRuntimesManifest>(&raw_manifest_data)?;
+
+    let runtimes = get_jvm_runtimes(&manifest_data, platform_str).await?;
+
+    let item = match runtimes.iter().any(|i| i == component) {
+        true => manifest_data.platforms[platform_str][component].first(),
+        false => None,
+    };
+    let Some(item) = item.cloned() else {
+        log::warn!("Jvm \"{component}\" not found for {platform_str}");
+        return Ok(None);
+    };
+
+    let platform_manifest = helpers::http::get(&item.manifest.url, Some(client)).await?;
+    let platform_manifest = serde_json::from_slice::<JavaRuntimeFiles>(&platform_manifest)?;
+
+    Ok(Some((item, platform_manifest)))
+}
+
+/// Directory holding runtime `component` and its marker files
+fn get_runtime_root(launcher: &Launcher, component: &str) -> Option<PathBuf> {
+    let path = launcher
+        .path
+        .join("runtime")
+        .join(component)
+        .join(get_jvm_platform()?);
+    Some(path)
+}
+
+fn get_file_mtime(metadata: &std::fs::Metadata) -> u128 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|time| time.as_nanos())
+        .unwrap_or_default()
+}
+
+/// Write `.version` and `<component>.sha1` markers of the completed install
+async fn write_markers(
+    root: &Path,
+    component: &str,
+    version: &str,
+    manifest: &JavaRuntimeFiles,
+) -> Result<(), Error> {
+    let base_path = root.join(component);
+    let mut keys: Vec<&String> = manifest.files.keys().collect();
+    keys.sort();
+
+    let mut lines = Vec::new();
+    for key in keys {
+        let item = &manifest.files[key];
+        let Some(downloads) = &item.downloads else {
+            continue;
+        };
+        if item.item_type != "file" {
+            continue;
+        }
+
+        let metadata = tokio::fs::metadata(base_path.join(key)).await?;
+        lines.push(format!(
+            "{key} /#// {} {}",
+            downloads.raw.sha1,
+            get_file_mtime(&metadata)
+        ));
+    }
+
+    tokio::fs::write(root.join(format!("{component}.sha1")), lines.join("\n")).await?;
+    tokio::fs::write(root.join(".version"), version).await?;
+    Ok(())
+}
+
+/// Check markers of runtime `component` against the files on disk
+///
+/// Only presence and modification time of every file are compared,
+/// use [`repair_jvm_runtime`] to check the hashes
+pub async fn verify_jvm_runtime(launcher: &Launcher, component: &str) -> Result<bool, Error> {
+    let Some(root) = get_runtime_root(launcher, component) else {
+        return Ok(false);
+    };
+    let base_path = root.join(component);
+
+    let (Ok(version), Ok(sha1_list)) = (
+        tokio::fs::read_to_string(root.join(".version")).await,
+        tokio::fs::read_to_string(root.join(format!("{component}.sha1"))).await,
+    ) else {
+        return Ok(false);
+    };
+    if version.trim().is_empty() {
+        return Ok(false);
+    }
+
+    for line in sha1_list.lines() {
+        let Some((key, rest)) = line.split_once(" /#// ") else {
+            log::warn!("Malformed line in {component}.sha1: {line}");
+            return Ok(false);
+        };
+        let mtime = rest.split_once(' ').map(|(_, mtime)| mtime).unwrap_or("");
+
+        let Ok(metadata) = tokio::fs::metadata(base_path.join(key)).await else {
+            log::warn!("Runtime file \"{key}\" is missing");
+            return Ok(false);
+        };
+        if get_file_mtime(&metadata).to_string() != mtime {
+            log::warn!("Runtime file \"{key}\" was modified");
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Install files of runtime `component` and return its java home
+///
+/// Files that already exist are kept as is, unless `check_hashes` is set,
+/// in that case every file is compared against the manifest sha1
+async fn install_runtime_files(
+    launcher: &Launcher,
+    component: &str,
+    check_hashes: bool,
+) -> Result<Option<PathBuf>, Error> {
+    let client = reqwest::Client::builder().build()?;
+    let Some((item, platform_manifest)) = get_component_manifest(component, &client).await? else {
+        return Ok(None);
+    };
+    let Some(root) = get_runtime_root(launcher, component) else {
+        return Ok(None);
+    };
+    let base_path = &root.join(component);
+    tokio::fs::create_dir_all(&base_path).await?;
+
+    // Маркер удаляется до начала установки, чтобы прерванная установка
+    // не считалась завершенной
+    if let Err(err) = tokio::fs::remove_file(root.join(".version")).await
+        && err.kind() != std::io::ErrorKind::NotFound
+    {
+        return Err(err.into());
+    }
+
+    let mut links = HashMap::new();
+
+    for (key, value) in &platform_manifest.files {
+        let current_path = base_path.join(key);
+
+        match value.item_type.as_str() {
+            "file" => {
+                let downloads = value
+                    .downloads
+                    .as_ref()
+                    .ok_or_else(|| Error::Jvm(format!("File \"{key}\" has no downloads")))?;
+
+                if current_path.exists() {
+                    if !check_hashes
+                        || utils::get_file_sha1(&current_path).await? == downloads.raw.sha1
+                    {
+                        continue;
+                    }
+                    log::warn!("File \"{key}\" is corrupted, downloading again");
+                    tokio::fs::remove_file(&current_path).await?;
+                }
+                log::info!("Installing file: {key}");
+                let parent = current_path.parent().unwrap();
+                tokio::fs::create_dir_all(parent).await?;
+
+                download_file(&downloads.raw.url, &current_path, &client).await?;
+                if utils::get_file_sha1(&current_path).await? != downloads.raw.sha1 {
+                    tokio::fs::remove_file(&current_path).await?;
+                    return Err(Error::Jvm(format!("Hash mismatch for file \"{key}\"")));
+                }
+
+                utils::make_executable(&current_path).await?;
+            }
+            "directory" => {
+                if current_path.exists() {
+                    continue;
+                }
+                log::info!("Creating directory: {key}");
+
+                tokio::fs::create_dir_all(current_path).await?;
+            }
+            "link" => {
+                links.insert(key.as_str(), value.target.as_str());
+            }
+            _ => {}
+        }
+    }
+
+    // Ссылки создаются после загрузки всех файлов и в порядке зависимостей,
+    // чтобы при копировании цель уже существовала
+    for key in sort_links(&links, &platform_manifest)? {
+        create_link(base_path, key, links[key], &platform_manifest).await?;
+    }
+
+    write_markers(&root, component, &item.version.name, &platform_manifest).await?;
+
+    Ok(Some(base_path.clone()))
+}
+
+/// Install runtime `component` and return its java home
+///
+/// `None` is returned if Mojang has no such runtime for the platform
+pub async fn install_jvm_component(
+    launcher: &Launcher,
+    component: &str,
+) -> Result<Option<PathBuf>, Error> {
+    let Some(root) = get_runtime_root(launcher, component) else {
+        return Ok(None);
+    };
+
+    if verify_jvm_runtime(launcher, component).await? {
+        log::info!("Jvm already installed");
+        return Ok(Some(root.join(component)));
+    }
+
+    // Без маркеров неизвестно, была ли установка завершена,
+    // поэтому уже существующие файлы проверяются по хешу
+    let has_files = root.join(component).exists();
+    let home = install_runtime_files(launcher, component, has_files).await?;
+
+    if home.is_some() {
+        log::info!("Jvm installed!");
+    }
+
+    Ok(home)
+}
+
+pub async fn install_jvm_runtime(launcher: &Launcher, info: &VersionJson) -> Result<(), Error> {
+    let component = &info.java_version.component;
+
+    match install_jvm_component(launcher, component).await? {
+        Some(_) => Ok(()),
+        None => Err(Error::Jvm(format!("Jvm \"{component}\" not found"))),
+    }
+}
+
+/// Re-validate every file of runtime `component` against the manifest
+/// and download the missing or corrupted ones
+pub async fn repair_jvm_runtime(launcher: &Launcher, component: &str) -> Result<(), Error> {
+    log::info!("Repairing jvm runtime \"{component}\"...");
+    if install_runtime_files(launcher, component, true)
+        .await?
+        .is_none()
+    {
+        return Err(Error::Jvm(format!("Jvm \"{component}\" not found")));
+    }
+    log::info!("Jvm repaired!");
+    Ok(())
+}
+
+/// Runtimes from the official Mojang manifest
+pub struct MojangProvider;
+
+impl JvmProvider for MojangProvider {
+    fn name(&self) -> &str {
+        "mojang"
+    }
+
+    fn install<'a>(
+        &'a self,
+        launcher: &'a Launcher,
+        java_version: &'a _JsonJavaVersion,
+    ) -> BoxFuture<'a, Result<Option<PathBuf>, Error>> {
+        Box::pin(install_jvm_component(launcher, &java_version.component))
+    }
+}
@@ -0,0 +1,17 @@
+use super::MetaApi;
+
+const FABRIC_META_URL: &str = "https://meta.fabricmc.net";
+
+/// Fabric Meta api at `base_url`, e.g. a mirror
+pub fn get_meta(base_url: &str) -> MetaApi {
+    MetaApi {
+        base_url: base_url.trim_end_matches('/').to_string(),
+        api_version: "v2".to_string(),
+        name: "Fabric".to_string(),
+        id_prefix: "fabric-loader".to_string(),
+    }
+}
+
+pub fn get_default_meta() -> MetaApi {
+    get_meta(FABRIC_META_URL)
+}
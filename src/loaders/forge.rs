@@ -0,0 +1,835 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    path::{self, Path, PathBuf},
+};
+
+use serde_json::Value;
+use tokio::process::Command;
+use zip::ZipArchive;
+
+use super::{check_version_id, is_version_installed};
+use crate::{
+    helpers,
+    install::{self, FileCheck},
+    instances::LoaderKind,
+    internal_types::{
+        shared::{VersionJsonLibrary, get_maven_path},
+        shared_forge::{
+            _JsonInstallProfileProcessor, JsonInstallProfile, JsonLegacyInstallProfile,
+        },
+    },
+    jvm::{self, discovery::JavaInstallation},
+    types::{Error, Launcher},
+    utils,
+};
+
+const FORGE_MAVEN_URL: &str = "https://maven.minecraftforge.net";
+const NEOFORGE_MAVEN_URL: &str = "https://maven.neoforged.net/releases";
+
+/// Installer of Forge and NeoForge, maven url can be replaced with a mirror
+#[derive(Debug, Clone)]
+pub struct ForgeInstaller {
+    pub kind: LoaderKind,
+    pub maven_url: String,
+}
+
+/// Opened installer jar
+struct Installer {
+    path: PathBuf,
+    archive: ZipArchive<fs::File>,
+}
+
+impl Installer {
+    fn open(path: &Path) -> Result<Self, Error> {
+        Ok(Installer {
+            path: path.to_path_buf(),
+            archive: ZipArchive::new(fs::File::open(path)?)?,
+        })
+    }
+
+    fn has_file(&self, name: &str) -> bool {
+        self.archive
+            .index_for_name(name.trim_start_matches('/'))
+            .is_some()
+    }
+
+    fn read_file(&mut self, name: &str) -> Result<Vec<u8>, Error> {
+        let mut file = self
+            .archive
+            .by_name(name.trim_start_matches('/'))
+            .map_err(|_| Error::Loader(format!("{name} not found in {:?}", self.path)))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn extract_file(&mut self, name: &str, dest: &Path) -> Result<(), Error> {
+        let mut file = self
+            .archive
+            .by_name(name.trim_start_matches('/'))
+            .map_err(|_| Error::Loader(format!("{name} not found in {:?}", self.path)))?;
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut fs::File::create(dest)?)?;
+        Ok(())
+    }
+}
+
+fn get_library_path(launcher: &Launcher, name: &str) -> Result<PathBuf, Error> {
+    let path = get_maven_path(name)
+        .ok_or_else(|| Error::Loader(format!("Invalid maven coordinates {name}")))?;
+    Ok(path::absolute(launcher.path.join("libraries").join(path))?)
+}
+
+/// Read `Main-Class` from the jar manifest
+fn get_main_class(jar: &Path) -> Result<String, Error> {
+    let mut archive = ZipArchive::new(fs::File::open(jar)?)?;
+    let mut manifest = String::new();
+    archive
+        .by_name("META-INF/MANIFEST.MF")
+        .map_err(|_| Error::Loader(format!("Manifest of {jar:?} not found")))?
+        .read_to_string(&mut manifest)?;
+
+    // Длинные значения переносятся на строки, начинающиеся с пробела
+    let manifest = manifest.replace("\r\n", "\n").replace("\n ", "");
+    manifest
+        .lines()
+        .find_map(|line| line.strip_prefix("Main-Class:"))
+        .map(|i| i.trim().to_string())
+        .ok_or_else(|| Error::Loader(format!("Main class of {jar:?} not found")))
+}
+
+/// Install libraries of the installer
+///
+/// Libraries without url are taken from the `maven/` directory of the installer,
+/// the rest of them are produced by the processors
+async fn install_libraries(
+    launcher: &Launcher,
+    installer: &mut Installer,
+    libraries: &[VersionJsonLibrary],
+) -> Result<(), Error> {
+    let client = reqwest::Client::builder().build()?;
+    for lib in libraries {
+        let Some(artifact) = lib.get_artifact() else {
+            continue;
+        };
+        let path = launcher.path.join("libraries").join(&artifact.path);
+        let sha1 = artifact.sha1.as_deref();
+        if install::is_file_valid(&path, sha1, FileCheck::Hash).await? {
+            continue;
+        }
+
+        let bundled = format!("maven/{}", artifact.path);
+        if installer.has_file(&bundled) {
+            installer.extract_file(&bundled, &path)?;
+        } else if !artifact.url.is_empty() {
+            log::info!("Installing lib \"{}\"", lib.name);
+            tokio::fs::create_dir_all(path.parent().unwrap_or(&launcher.path)).await?;
+            install::download_library(&artifact.url, &path, sha1, FileCheck::Hash, &client).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs `processors` of the install profile
+struct ProcessorRunner<'a> {
+    launcher: &'a Launcher,
+    java: &'a JavaInstallation,
+    /// Resolved `data` entries and built-in variables
+    data: HashMap<String, String>,
+}
+
+impl ProcessorRunner<'_> {
+    /// Resolve argument: `{KEY}` is a data entry, `[coords]` is a library path,
+    /// `'value'` and anything else is a literal
+    fn resolve(&self, value: &str) -> Result<String, Error> {
+        if let Some(key) = value.strip_prefix('{').and_then(|i| i.strip_suffix('}')) {
+            return self
+                .data
+                .get(key)
+                .cloned()
+                .ok_or_else(|| Error::Loader(format!("Unknown processor variable {key}")));
+        }
+        if let Some(name) = value.strip_prefix('[').and_then(|i| i.strip_suffix(']')) {
+            return Ok(get_library_path(self.launcher, name)?
+                .to_string_lossy()
+                .to_string());
+        }
+        Ok(value.trim_matches('\'').to_string())
+    }
+
+    fn get_outputs(
+        &self,
+        processor: &_JsonInstallProfileProcessor,
+    ) -> Result<Vec<(PathBuf, String)>, Error> {
+        processor
+            .outputs
+            .iter()
+            .map(|(path, sha1)| Ok((PathBuf::from(self.resolve(path)?), self.resolve(sha1)?)))
+            .collect()
+    }
+
+    async fn is_done(&self, processor: &_JsonInstallProfileProcessor) -> Result<bool, Error> {
+        let outputs = self.get_outputs(processor)?;
+        if outputs.is_empty() {
+            return Ok(false);
+        }
+        for (path, sha1) in outputs {
+            if !path.exists() || utils::get_file_sha1(&path).await? != sha1 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn run(&self, processor: &_JsonInstallProfileProcessor) -> Result<(), Error> {
+        if self.is_done(processor).await? {
+            log::info!("Processor {} is already done", processor.jar);
+            return Ok(());
+        }
+
+        let jar = get_library_path(self.launcher, &processor.jar)?;
+        let main_class = get_main_class(&jar)?;
+        let mut classpath = vec![jar];
+        for name in &processor.classpath {
+            classpath.push(get_library_path(self.launcher, name)?);
+        }
+        let classpath = std::env::join_paths(classpath)
+            .map_err(|err| Error::Loader(format!("Invalid processor classpath: {err}")))?;
+        let args = processor
+            .args
+            .iter()
+            .map(|arg| self.resolve(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        log::info!("Running processor {}...", processor.jar);
+        let output = Command::new(&self.java.path)
+            .arg("-cp")
+            .arg(classpath)
+            .arg(&main_class)
+            .args(&args)
+            .current_dir(&self.launcher.path)
+            .output()
+            .await?;
+        if !output.status.success() {
+            log::error!("{}", String::from_utf8_lossy(&output.stdout));
+            log::error!("{}", String::from_utf8_lossy(&output.stderr));
+            return Err(Error::Loader(format!(
+                "Processor {} failed with {}",
+                processor.jar, output.status
+            )));
+        }
+
+        for (path, sha1) in self.get_outputs(processor)? {
+            let actual = utils::get_file_sha1(&path).await?;
+            if actual != sha1 {
+                let _ = tokio::fs::remove_file(&path).await;
+                return Err(Error::Loader(format!(
+                    "Output {path:?} of processor {} has sha1 {actual}, expected {sha1}",
+                    processor.jar
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ForgeInstaller {
+    pub fn new(kind: LoaderKind) -> Self {
+        let maven_url = match kind {
+            LoaderKind::NeoForge => NEOFORGE_MAVEN_URL,
+            _ => FORGE_MAVEN_URL,
+        };
+        ForgeInstaller {
+            kind,
+            maven_url: maven_url.to_string(),
+        }
+    }
+
+    /// Maven group of the installer, NeoForge for 1.20.1 uses the old artifact
+    fn get_group(&self, game_version: &str) -> &'static str {
+        match self.kind {
+            LoaderKind::NeoForge if game_version != "1.20.1" => "net.neoforged:neoforge",
+            LoaderKind::NeoForge => "net.neoforged:forge",
+            _ => "net.minecraftforge:forge",
+        }
+    }
+
+    /// Find installer version in the maven metadata, legacy Forge versions have
+    /// a branch suffix, e.g. `1.7.10-10.13.4.1614-1.7.10`
+    async fn find_branch_version(&self, group: &str, full_version: &str) -> Option<String> {
+        let url = format!(
+            "{}/{}/maven-metadata.xml",
+            self.maven_url,
+            group.replace(['.', ':'], "/")
+        );
+        let metadata = match helpers::http::get(&url, None).await {
+            Ok(metadata) => String::from_utf8_lossy(&metadata).to_string(),
+            Err(err) => {
+                log::warn!("Failed to get {url}: {err:?}");
+                return None;
+            }
+        };
+        let branch_prefix = format!("{full_version}-");
+        metadata
+            .split("<version>")
+            .skip(1)
+            .filter_map(|i| i.split_once("</version>"))
+            .map(|(version, _)| version.trim())
+            .find(|i| i.starts_with(&branch_prefix))
+            .map(|i| i.to_string())
+    }
+
+    /// Get maven coordinates of the installer
+    async fn get_installer_name(
+        &self,
+        launcher: &Launcher,
+        game_version: &str,
+        loader_version: &str,
+    ) -> Result<String, Error> {
+        let group = self.get_group(game_version);
+        if group == "net.neoforged:neoforge" {
+            return Ok(format!("{group}:{loader_version}:installer"));
+        }
+
+        let full_version = match loader_version.starts_with(&format!("{game_version}-")) {
+            true => loader_version.to_string(),
+            false => format!("{game_version}-{loader_version}"),
+        };
+        // Сначала ищется уже скачанный установщик, чтобы не ходить в сеть
+        let candidates = [
+            format!("{group}:{full_version}:installer"),
+            format!("{group}:{full_version}-{game_version}:installer"),
+        ];
+        for name in &candidates {
+            if get_library_path(launcher, name)?.exists() {
+                return Ok(name.clone());
+            }
+        }
+
+        let maven_path = get_maven_path(&candidates[0]).unwrap_or_default();
+        let url = format!("{}/{maven_path}", self.maven_url);
+        let client = reqwest::Client::builder().build()?;
+        if client.head(&url).send().await?.status().is_success() {
+            return Ok(candidates[0].clone());
+        }
+        match self.find_branch_version(group, &full_version).await {
+            Some(version) => Ok(format!("{group}:{version}:installer")),
+            None => Ok(candidates[0].clone()),
+        }
+    }
+
+    /// Install Forge / NeoForge for `game_version` and return id of the inheriting version
+    ///
+    /// The id is taken from the installer, so already installed versions are skipped
+    /// without running the installer again
+    pub async fn install(
+        &self,
+        launcher: &Launcher,
+        game_version: &str,
+        loader_version: &str,
+    ) -> Result<String, Error> {
+        let installer_name = self
+            .get_installer_name(launcher, game_version, loader_version)
+            .await?;
+        let path = get_library_path(launcher, &installer_name)?;
+        let maven_path = get_maven_path(&installer_name).unwrap_or_default();
+        if !path.exists() {
+            tokio::fs::create_dir_all(path.parent().unwrap_or(&launcher.path)).await?;
+            let client = reqwest::Client::builder().build()?;
+            let url = format!("{}/{maven_path}", self.maven_url);
+            install::download_file(&url, &path, &client).await?;
+        }
+
+        let mut installer = Installer::open(&path)?;
+        let profile: Value = serde_json::from_slice(&installer.read_file("install_profile.json")?)?;
+        let id = get_version_id(&mut installer, &profile)?;
+        if is_version_installed(launcher, &id) {
+            return Ok(id);
+        }
+
+        log::info!(
+            "Installing {:?} {loader_version} for {game_version}...",
+            self.kind
+        );
+        let id = match profile.get("install") {
+            Some(_) => {
+                install_legacy(launcher, &mut installer, serde_json::from_value(profile)?).await?
+            }
+            None => {
+                install_modern(launcher, &mut installer, serde_json::from_value(profile)?).await?
+            }
+        };
+
+        log::info!("{:?} installed!", self.kind);
+        Ok(id)
+    }
+}
+
+/// Get id of the version json installed by the installer
+fn get_version_id(installer: &mut Installer, profile: &Value) -> Result<String, Error> {
+    let version = match profile.get("versionInfo") {
+        Some(version) => version.clone(),
+        None => {
+            let json = profile["json"]
+                .as_str()
+                .ok_or_else(|| Error::Loader("Install profile has no version json".to_string()))?;
+            serde_json::from_slice(&installer.read_file(json)?)?
+        }
+    };
+    version["id"]
+        .as_str()
+        .map(|i| i.to_string())
+        .ok_or_else(|| Error::Loader("Version json of the installer has no id".to_string()))
+}
+
+/// Write version json of the loader into `versions/<id>`
+///
+/// Only the json is written, the parent version is installed with the instance
+async fn install_version_json(launcher: &Launcher, data: &[u8]) -> Result<String, Error> {
+    let value: Value = serde_json::from_slice(data)?;
+    let id = value["id"]
+        .as_str()
+        .ok_or_else(|| Error::Loader("Version json of the installer has no id".to_string()))?
+        .to_string();
+    check_version_id(&id)?;
+
+    let path = launcher.path.join("versions").join(&id);
+    tokio::fs::create_dir_all(&path).await?;
+    tokio::fs::write(
+        path.join(format!("{id}.json")),
+        serde_json::to_vec_pretty(&value)?,
+    )
+    .await?;
+    Ok(id)
+}
+
+/// Resolve `data` of the install profile and run its client processors
+///
+/// Files of the installer referenced by `data` are extracted into a temporary directory
+async fn run_processors(
+    launcher: &Launcher,
+    installer: &mut Installer,
+    profile: &JsonInstallProfile,
+    java: &JavaInstallation,
+    client_jar: &Path,
+) -> Result<(), Error> {
+    let launcher_dir = path::absolute(&launcher.path)?;
+    let tmp_dir = launcher_dir.join(".forge-installer");
+    let client_jar = path::absolute(client_jar)?;
+
+    let mut data = HashMap::from([
+        ("SIDE".to_string(), "client".to_string()),
+        ("MINECRAFT_VERSION".to_string(), profile.minecraft.clone()),
+        (
+            "MINECRAFT_JAR".to_string(),
+            client_jar.to_string_lossy().to_string(),
+        ),
+        (
+            "ROOT".to_string(),
+            launcher_dir.to_string_lossy().to_string(),
+        ),
+        (
+            "INSTALLER".to_string(),
+            path::absolute(&installer.path)?
+                .to_string_lossy()
+                .to_string(),
+        ),
+        (
+            "LIBRARY_DIR".to_string(),
+            launcher_dir.join("libraries").to_string_lossy().to_string(),
+        ),
+    ]);
+    for (key, value) in &profile.data {
+        let value = &value.client;
+        let resolved = if let Some(name) = value.strip_prefix('[').and_then(|i| i.strip_suffix(']'))
+        {
+            get_library_path(launcher, name)?
+                .to_string_lossy()
+                .to_string()
+        } else if value.starts_with('/') {
+            // Файлы из установщика распаковываются во временную директорию
+            let dest = tmp_dir.join(value.trim_start_matches('/'));
+            installer.extract_file(value, &dest)?;
+            dest.to_string_lossy().to_string()
+        } else {
+            // Литералы записаны в кавычках: `'value'`
+            value.trim_matches('\'').to_string()
+        };
+        data.insert(key.clone(), resolved);
+    }
+
+    let runner = ProcessorRunner {
+        launcher,
+        java,
+        data,
+    };
+    let result = async {
+        for processor in &profile.processors {
+            let is_client = processor
+                .sides
+                .as_ref()
+                .is_none_or(|sides| sides.iter().any(|i| i == "client"));
+            if is_client {
+                runner.run(processor).await?;
+            }
+        }
+        Ok::<(), Error>(())
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&tmp_dir).await;
+    result
+}
+
+/// Install 1.13+ Forge: libraries, processors and the version json
+async fn install_modern(
+    launcher: &Launcher,
+    installer: &mut Installer,
+    profile: JsonInstallProfile,
+) -> Result<String, Error> {
+    // Процессоры патчат клиент, поэтому он должен быть установлен заранее
+    let game_info = install::get_version_json(launcher, &profile.minecraft).await?;
+    install::install_client(launcher, &game_info).await?;
+    let java = jvm::get_java(launcher, &game_info).await?;
+
+    let version_data = installer.read_file(&profile.json)?;
+    let version: Value = serde_json::from_slice(&version_data)?;
+    let version_libraries: Vec<VersionJsonLibrary> =
+        serde_json::from_value(version["libraries"].clone()).unwrap_or_default();
+    install_libraries(launcher, installer, &profile.libraries).await?;
+    install_libraries(launcher, installer, &version_libraries).await?;
+
+    let client_jar = launcher
+        .path
+        .join("versions")
+        .join(game_info.get_jar_id())
+        .join(format!("{}.jar", game_info.get_jar_id()));
+    run_processors(launcher, installer, &profile, &java, &client_jar).await?;
+
+    install_version_json(launcher, &version_data).await
+}
+
+/// Install legacy Forge: universal jar is put into libraries, version json is taken
+/// from `versionInfo` of the install profile
+async fn install_legacy(
+    launcher: &Launcher,
+    installer: &mut Installer,
+    profile: JsonLegacyInstallProfile,
+) -> Result<String, Error> {
+    let path = get_library_path(launcher, &profile.install.path)?;
+    installer.extract_file(&profile.install.file_path, &path)?;
+
+    let mut version = profile.version_info;
+    let Some(version_object) = version.as_object_mut() else {
+        return Err(Error::Loader(
+            "Invalid versionInfo of the installer".to_string(),
+        ));
+    };
+    version_object
+        .entry("inheritsFrom")
+        .or_insert_with(|| Value::String(profile.install.minecraft.clone()));
+    // Библиотеки, нужные только серверу, отмечены `clientreq: false`
+    if let Some(Value::Array(libraries)) = version_object.get_mut("libraries") {
+        libraries.retain(|lib| lib["clientreq"].as_bool() != Some(false));
+        for lib in libraries.iter_mut() {
+            if let Some(lib) = lib.as_object_mut() {
+                // Несколько сумм бывает у библиотек с упакованными вариантами,
+                // тогда неизвестно, какая из них относится к jar
+                if let Some(Value::Array(checksums)) = lib.get("checksums")
+                    && let [Value::String(sha1)] = checksums.as_slice()
+                {
+                    let sha1 = sha1.clone();
+                    lib.entry("sha1").or_insert(Value::String(sha1));
+                }
+                lib.remove("clientreq");
+                lib.remove("serverreq");
+            }
+        }
+    }
+
+    install_version_json(launcher, &serde_json::to_vec(&version)?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use serde_json::json;
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+    use crate::helpers::testing::{MockServer, TempLauncher};
+
+    const LEGACY_VERSION: &str = "1.7.10-10.13.4.1614-1.7.10";
+    const LEGACY_ID: &str = "1.7.10-Forge10.13.4.1614-1.7.10";
+
+    fn get_legacy_installer() -> Vec<u8> {
+        let profile = json!({
+            "install": {
+                "path": format!("net.minecraftforge:forge:{LEGACY_VERSION}"),
+                "filePath": format!("forge-{LEGACY_VERSION}-universal.jar"),
+                "minecraft": "1.7.10",
+            },
+            "versionInfo": {
+                "id": LEGACY_ID,
+                "libraries": [
+                    { "name": format!("net.minecraftforge:forge:{LEGACY_VERSION}") },
+                    { "name": "lzma:lzma:0.0.1", "checksums": ["abc"] },
+                    {
+                        "name": "org.scala-lang:scala-library:2.11.1",
+                        "checksums": ["def", "123"],
+                        "clientreq": true,
+                    },
+                    { "name": "server:only:1", "clientreq": false, "serverreq": true },
+                ],
+            },
+        });
+
+        let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        zip.start_file("install_profile.json", options).unwrap();
+        zip.write_all(profile.to_string().as_bytes()).unwrap();
+        zip.start_file(format!("forge-{LEGACY_VERSION}-universal.jar"), options)
+            .unwrap();
+        zip.write_all(b"universal").unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn legacy_forge_is_installed_once() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let metadata = format!(
+            "<metadata><versioning><versions><version>1.7.10-10.13.4.1558-1.7.10</version>\
+             <version>{LEGACY_VERSION}</version></versions></versioning></metadata>"
+        );
+        server.route(
+            "/net/minecraftforge/forge/maven-metadata.xml",
+            200,
+            metadata,
+        );
+        let installer_path = format!(
+            "/net/minecraftforge/forge/{LEGACY_VERSION}/forge-{LEGACY_VERSION}-installer.jar"
+        );
+        server.route(&installer_path, 200, get_legacy_installer());
+
+        let installer = ForgeInstaller {
+            kind: LoaderKind::Forge,
+            maven_url: server.url.clone(),
+        };
+        let id = installer
+            .install(&temp.launcher, "1.7.10", "10.13.4.1614")
+            .await
+            .unwrap();
+        assert_eq!(id, LEGACY_ID);
+
+        let universal = get_library_path(
+            &temp.launcher,
+            &format!("net.minecraftforge:forge:{LEGACY_VERSION}"),
+        )
+        .unwrap();
+        assert_eq!(fs::read(universal).unwrap(), b"universal");
+
+        let json_path = temp
+            .path()
+            .join("versions")
+            .join(LEGACY_ID)
+            .join(format!("{LEGACY_ID}.json"));
+        let version: Value = serde_json::from_slice(&fs::read(json_path).unwrap()).unwrap();
+        assert_eq!(version["inheritsFrom"], "1.7.10");
+        let libraries = version["libraries"].as_array().unwrap();
+        assert_eq!(libraries.len(), 3);
+        assert_eq!(libraries[1]["sha1"], "abc");
+        assert_eq!(libraries[1]["checksums"], json!(["abc"]));
+        assert!(libraries[2].get("sha1").is_none());
+        assert!(libraries[2].get("clientreq").is_none());
+
+        let requests = server.requests().len();
+        let id = installer
+            .install(&temp.launcher, "1.7.10", "10.13.4.1614")
+            .await
+            .unwrap();
+        assert_eq!(id, LEGACY_ID);
+        assert_eq!(server.requests().len(), requests);
+    }
+
+    #[cfg(unix)]
+    fn write_stub_java(temp: &TempLauncher) -> (JavaInstallation, PathBuf) {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Записывает аргументы и содержимое патча, создает файл после `--output`
+        let record = temp.path().join("argv.txt");
+        let script = format!(
+            "#!/bin/sh\n\
+             printf '%s\\n' \"$@\" >> '{record}'\n\
+             while [ $# -gt 0 ]; do\n\
+               case \"$1\" in\n\
+                 --patch) cat \"$2\" >> '{record}'; echo >> '{record}' ;;\n\
+                 --output) mkdir -p \"$(dirname \"$2\")\"; printf patched > \"$2\" ;;\n\
+               esac\n\
+               shift\n\
+             done\n",
+            record = record.display()
+        );
+        let path = temp.path().join("java");
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let java = JavaInstallation {
+            path,
+            version: "17.0.1".to_string(),
+            major_version: 17,
+            vendor: "Test".to_string(),
+            arch: "x86_64".to_string(),
+        };
+        (java, record)
+    }
+
+    #[cfg(unix)]
+    fn get_modern_profile(output_sha1: &str) -> JsonInstallProfile {
+        serde_json::from_value(json!({
+            "minecraft": "1.20.1",
+            "json": "/version.json",
+            "data": {
+                "MAPPINGS": { "client": "[de.oceanlabs.mcp:mcp_config:1.20.1:mappings@txt]", "server": "" },
+                "BINPATCH": { "client": "/data/client.lzma", "server": "" },
+                "NAME": { "client": "'forge client'", "server": "" },
+                "PATCHED": { "client": "[net.minecraftforge:forge:1.20.1-47.2.0:client]", "server": "" },
+                "PATCHED_SHA": { "client": format!("'{output_sha1}'"), "server": "" },
+            },
+            "processors": [
+                {
+                    "jar": "net.minecraftforge:binarypatcher:1.1.1",
+                    "classpath": ["net.sf.jopt-simple:jopt-simple:5.0.4"],
+                    "args": [
+                        "--side", "{SIDE}",
+                        "--clean", "{MINECRAFT_JAR}",
+                        "--patch", "{BINPATCH}",
+                        "--mappings", "{MAPPINGS}",
+                        "--lib", "[com.example:lib:1.0]",
+                        "--name", "{NAME}",
+                        "--output", "{PATCHED}",
+                    ],
+                    "outputs": { "{PATCHED}": "{PATCHED_SHA}" },
+                },
+                {
+                    "jar": "net.minecraftforge:binarypatcher:1.1.1",
+                    "args": ["--server-only"],
+                    "sides": ["server"],
+                },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[cfg(unix)]
+    fn write_modern_installer(temp: &TempLauncher) -> Installer {
+        let path = temp.path().join("installer.jar");
+        let mut zip = ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file("data/client.lzma", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"binpatch").unwrap();
+        zip.finish().unwrap();
+
+        let processor =
+            get_library_path(&temp.launcher, "net.minecraftforge:binarypatcher:1.1.1").unwrap();
+        fs::create_dir_all(processor.parent().unwrap()).unwrap();
+        let mut zip = ZipWriter::new(fs::File::create(processor).unwrap());
+        zip.start_file("META-INF/MANIFEST.MF", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"Manifest-Version: 1.0\r\nMain-Class: net.minecraftforge.binarypatcher.Con\r\n sole\r\n")
+            .unwrap();
+        zip.finish().unwrap();
+
+        Installer::open(&path).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn modern_processors_are_run() {
+        let temp = TempLauncher::new();
+        let (java, record) = write_stub_java(&temp);
+        let mut installer = write_modern_installer(&temp);
+        let profile = get_modern_profile(&utils::get_sha1(b"patched"));
+        let client_jar = temp.path().join("versions/1.20.1/1.20.1.jar");
+
+        run_processors(&temp.launcher, &mut installer, &profile, &java, &client_jar)
+            .await
+            .unwrap();
+
+        let lib = |name: &str| {
+            get_library_path(&temp.launcher, name)
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        };
+        let absolute = |path: &Path| path::absolute(path).unwrap().to_string_lossy().to_string();
+        let classpath = std::env::join_paths([
+            lib("net.minecraftforge:binarypatcher:1.1.1"),
+            lib("net.sf.jopt-simple:jopt-simple:5.0.4"),
+        ])
+        .unwrap();
+        let patched = lib("net.minecraftforge:forge:1.20.1-47.2.0:client");
+        let expected = [
+            "-cp",
+            &classpath.to_string_lossy(),
+            "net.minecraftforge.binarypatcher.Console",
+            "--side",
+            "client",
+            "--clean",
+            &absolute(&client_jar),
+            "--patch",
+            &absolute(&temp.path().join(".forge-installer/data/client.lzma")),
+            "--mappings",
+            &lib("de.oceanlabs.mcp:mcp_config:1.20.1:mappings@txt"),
+            "--lib",
+            &lib("com.example:lib:1.0"),
+            "--name",
+            "forge client",
+            "--output",
+            &patched,
+            // Содержимое патча, распакованного из установщика
+            "binpatch",
+        ];
+        assert_eq!(
+            fs::read_to_string(&record)
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(fs::read(&patched).unwrap(), b"patched");
+        assert!(!temp.path().join(".forge-installer").exists());
+
+        // Процессор с готовым выходом не запускается повторно
+        fs::remove_file(&record).unwrap();
+        run_processors(&temp.launcher, &mut installer, &profile, &java, &client_jar)
+            .await
+            .unwrap();
+        assert!(!record.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn invalid_processor_output_is_removed() {
+        let temp = TempLauncher::new();
+        let (java, _) = write_stub_java(&temp);
+        let mut installer = write_modern_installer(&temp);
+        let profile = get_modern_profile(&utils::get_sha1(b"expected"));
+        let client_jar = temp.path().join("versions/1.20.1/1.20.1.jar");
+
+        let result =
+            run_processors(&temp.launcher, &mut installer, &profile, &java, &client_jar).await;
+        assert!(matches!(result, Err(Error::Loader(_))));
+        let patched = get_library_path(
+            &temp.launcher,
+            "net.minecraftforge:forge:1.20.1-47.2.0:client",
+        )
+        .unwrap();
+        assert!(!patched.exists());
+        assert!(!temp.path().join(".forge-installer").exists());
+    }
+}
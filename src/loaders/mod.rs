@@ -0,0 +1,184 @@
+pub mod fabric;
+pub mod forge;
+pub mod quilt;
+
+use serde_json::Value;
+
+use crate::{
+    helpers, install,
+    instances::{Loader, LoaderKind},
+    internal_types::shared_loaders::JsonMetaLoaderVersion,
+    types::{Error, Launcher},
+};
+
+/// Loader version available for a game version
+#[derive(Debug, Clone)]
+pub struct LoaderVersion {
+    pub version: String,
+    pub stable: bool,
+}
+
+/// Fabric Meta-compatible api, used by Fabric and Quilt
+///
+/// Loaders are installed as inheriting versions with maven libraries
+#[derive(Debug, Clone)]
+pub struct MetaApi {
+    pub base_url: String,
+    /// Api version path, `v2` for Fabric and `v3` for Quilt
+    pub api_version: String,
+    /// Loader name for logs
+    pub name: String,
+    /// Prefix of the version id, e.g. `fabric-loader`
+    pub id_prefix: String,
+}
+
+/// Check if the inheriting version `id` is already installed
+fn is_version_installed(launcher: &Launcher, id: &str) -> bool {
+    launcher
+        .path
+        .join("versions")
+        .join(id)
+        .join(format!("{id}.json"))
+        .exists()
+}
+
+/// Check that the version id from the remote profile can be used as a directory name
+fn check_version_id(id: &str) -> Result<(), Error> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+        return Err(Error::Loader(format!("Invalid version id {id:?}")));
+    }
+    Ok(())
+}
+
+impl MetaApi {
+    fn get_url(&self, path: &str) -> String {
+        format!(
+            "{}/{}/versions/loader/{path}",
+            self.base_url, self.api_version
+        )
+    }
+
+    /// Get loader versions compatible with `game_version`, newest first
+    pub async fn get_loader_versions(
+        &self,
+        game_version: &str,
+    ) -> Result<Vec<LoaderVersion>, Error> {
+        let data = helpers::http::get(&self.get_url(game_version), None).await?;
+        let versions: Vec<JsonMetaLoaderVersion> = serde_json::from_slice(&data)?;
+        Ok(versions
+            .into_iter()
+            .map(|i| LoaderVersion {
+                // Quilt Meta не отдает `stable`, нестабильные версии вида `0.20.0-beta.1`
+                stable: i
+                    .loader
+                    .stable
+                    .unwrap_or_else(|| !i.loader.version.contains('-')),
+                version: i.loader.version,
+            })
+            .collect())
+    }
+
+    /// Install loader for `game_version` and return id of the inheriting version
+    ///
+    /// The latest stable loader is used if `loader_version` is not set
+    pub async fn install(
+        &self,
+        launcher: &Launcher,
+        game_version: &str,
+        loader_version: Option<&str>,
+    ) -> Result<String, Error> {
+        let loader_version = match loader_version {
+            Some(version) => version.to_string(),
+            None => self
+                .get_loader_versions(game_version)
+                .await?
+                .into_iter()
+                .find(|i| i.stable)
+                .map(|i| i.version)
+                .ok_or_else(|| {
+                    Error::Loader(format!("No {} loader for {game_version} found", self.name))
+                })?,
+        };
+
+        let id = format!("{}-{loader_version}-{game_version}", self.id_prefix);
+        if is_version_installed(launcher, &id) {
+            return Ok(id);
+        }
+
+        log::info!(
+            "Installing {} {loader_version} for {game_version}...",
+            self.name
+        );
+        let url = self.get_url(&format!("{game_version}/{loader_version}/profile/json"));
+        let data = helpers::http::get(&url, None).await?;
+        let profile: Value = serde_json::from_slice(&data)?;
+        let id = profile["id"]
+            .as_str()
+            .ok_or_else(|| Error::Loader(format!("Profile from {url} has no id")))?
+            .to_string();
+        check_version_id(&id)?;
+
+        let path = launcher.path.join("versions").join(&id);
+        tokio::fs::create_dir_all(&path).await?;
+        tokio::fs::write(path.join(format!("{id}.json")), &data).await?;
+
+        let info = install::get_version_json(launcher, &id).await?;
+        install::install_libraries(launcher, &info, install::FileCheck::Hash).await?;
+        log::info!("{} installed!", self.name);
+
+        Ok(id)
+    }
+}
+
+/// Install `loader` for `game_version` and return id of the version to launch
+pub async fn install_loader(
+    launcher: &Launcher,
+    game_version: &str,
+    loader: &Loader,
+) -> Result<String, Error> {
+    let meta = match loader.kind {
+        LoaderKind::Fabric => fabric::get_default_meta(),
+        LoaderKind::Quilt => quilt::get_default_meta(),
+        kind @ (LoaderKind::Forge | LoaderKind::NeoForge) => {
+            return forge::ForgeInstaller::new(kind)
+                .install(launcher, game_version, &loader.version)
+                .await;
+        }
+    };
+    meta.install(launcher, game_version, Some(&loader.version))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::helpers::testing::{MockServer, TempLauncher};
+
+    #[test]
+    fn version_ids_with_separators_are_rejected() {
+        assert!(check_version_id("fabric-loader-0.16.14-1.21.8").is_ok());
+        for id in ["", "..", "../1.21.8", "a/b", "a\\b", "1.21.8/../../evil"] {
+            assert!(check_version_id(id).is_err(), "{id}");
+        }
+    }
+
+    #[tokio::test]
+    async fn remote_profile_id_is_validated() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        server.route_json(
+            "/v2/versions/loader/1.21.8/0.16.14/profile/json",
+            json!({ "id": "../../evil", "inheritsFrom": "1.21.8", "libraries": [] }),
+        );
+        let meta = fabric::get_meta(&server.url);
+
+        let result = meta
+            .install(&temp.launcher, "1.21.8", Some("0.16.14"))
+            .await;
+        assert!(matches!(result, Err(Error::Loader(_))));
+        assert!(!temp.path().join("evil").exists());
+        assert!(!temp.path().join("versions").exists());
+    }
+}
@@ -0,0 +1,17 @@
+use super::MetaApi;
+
+const QUILT_META_URL: &str = "https://meta.quiltmc.org";
+
+/// Quilt Meta api at `base_url`, e.g. a mirror
+pub fn get_meta(base_url: &str) -> MetaApi {
+    MetaApi {
+        base_url: base_url.trim_end_matches('/').to_string(),
+        api_version: "v3".to_string(),
+        name: "Quilt".to_string(),
+        id_prefix: "quilt-loader".to_string(),
+    }
+}
+
+pub fn get_default_meta() -> MetaApi {
+    get_meta(QUILT_META_URL)
+}
@@ -1,11 +1,25 @@
+use auth::{
+    Account, microsoft::MicrosoftAuth, offline::OfflineAccount, store::AccountStore,
+    yggdrasil::AuthlibInjector,
+};
+use futures::StreamExt;
 use types::Launcher;
 
+mod auth;
+mod crash;
+mod game_log;
 mod helpers;
+mod import;
 mod install;
+mod instances;
 mod internal_types;
 mod jvm;
+mod loaders;
+mod mods;
 mod natives;
+mod process;
 mod runtime;
+mod sessions;
 mod types;
 mod utils;
 
@@ -21,21 +35,48 @@ async fn main() -> Result<(), types::Error> {
     launcher.set_path("./.minecraft");
     launcher.init_path()?;
 
-    // Получение списка всех версий
-    let versions = utils::get_versions_list().await?;
+    // Получение инстанса
+    let mut instance = match instances::load_instance(&launcher, "1.21.8") {
+        Ok(instance) => instance,
+        Err(_) => instances::create_instance(&launcher, "1.21.8", "1.21.8")?,
+    };
 
-    // Получение определенной версии
-    if let Some(version) = versions.find_version("1.21.8") {
-        // Получение
-        let info = version.get_info(&launcher).await?;
+    let mut accounts = AccountStore::load(&launcher)?;
+    accounts.unlock_from_env()?;
+    if accounts.get_selected().is_none() {
+        accounts.add(Account::Offline(OfflineAccount::new("sigma_svinka")?));
+        accounts.save()?;
+    }
+    for account in accounts.get_expired() {
+        log::warn!("Account {} is expired, log in again", account.get_name());
+    }
+    // Токены Microsoft обновляются через приложение Azure из окружения
+    let ms_auth = MicrosoftAuth::from_env();
+    let auth = accounts
+        .prepare_launch(&launcher, ms_auth.as_ref(), &AuthlibInjector::default())
+        .await?;
 
-        install::install_libraries(&launcher, &info).await?;
-        install::install_assets(&launcher, &info).await?;
-        install::install_client(&launcher, &info).await?;
+    // run instance
+    let mut process = instances::launch_instance(&launcher, &mut instance, &auth).await?;
 
-        jvm::install_jvm_runtime(&launcher, &info).await?;
-        // run version
-        let command = runtime::get_command(&launcher, &info)?;
+    if let Some(mut events) = process.take_events() {
+        while let Some(event) = events.next().await {
+            // Вывод игры пишется в лог с ее уровнем, строки без уровня считаются info
+            let level = event.level.map_or(log::Level::Info, log::Level::from);
+            match event.thread {
+                Some(thread) => log::log!(target: "game", level, "[{thread}]: {}", event.message),
+                None => log::log!(target: "game", level, "{}", event.message),
+            }
+            if let Some(throwable) = event.throwable {
+                log::log!(target: "game", level, "{throwable}");
+            }
+        }
+    }
+    if let Some(crash) = crash::wait_for_crash(&process, &instance.get_game_dir()).await? {
+        log::error!("Game crashed: {:?}", crash.kind);
+        if let Some(report) = &crash.crash_report {
+            log::error!("Crash report: {report:?}");
+        }
     }
 
     Ok(())
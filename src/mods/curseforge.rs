@@ -0,0 +1,73 @@
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    auth::Secret,
+    helpers,
+    internal_types::shared_curseforge::{
+        JsonCurseForgeFile, JsonCurseForgeFilesRequest, JsonCurseForgeMod,
+        JsonCurseForgeModsRequest, JsonCurseForgeResponse,
+    },
+    types::Error,
+};
+
+pub const CURSEFORGE_API_URL: &str = "https://api.curseforge.com";
+
+/// CurseForge-compatible api, requests are signed with the user api key
+#[derive(Debug, Clone)]
+pub struct CurseForgeApi {
+    pub base_url: String,
+    pub api_key: Secret,
+}
+
+impl CurseForgeApi {
+    pub fn new(base_url: &str, api_key: Secret) -> Self {
+        CurseForgeApi {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    fn get_client(&self) -> Result<reqwest::Client, Error> {
+        let mut key = HeaderValue::from_str(self.api_key.expose())
+            .map_err(|_| Error::Mods("Invalid CurseForge api key".to_string()))?;
+        key.set_sensitive(true);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", key);
+        Ok(reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?)
+    }
+
+    async fn post<T: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &T,
+    ) -> Result<R, Error> {
+        let url = format!("{}/{path}", self.base_url);
+        let (status, data) = helpers::http::post_json(&url, body, &self.get_client()?).await?;
+        if !status.is_success() {
+            return Err(Error::Mods(format!(
+                "CurseForge api returned {status} for {url}"
+            )));
+        }
+        let response: JsonCurseForgeResponse<R> = serde_json::from_slice(&data)?;
+        Ok(response.data)
+    }
+
+    /// Get files by ids, unknown files are missing in the result
+    pub async fn get_files(&self, file_ids: &[u64]) -> Result<Vec<JsonCurseForgeFile>, Error> {
+        let body = JsonCurseForgeFilesRequest {
+            file_ids: file_ids.to_vec(),
+        };
+        self.post("v1/mods/files", &body).await
+    }
+
+    /// Get projects by ids, unknown projects are missing in the result
+    pub async fn get_mods(&self, mod_ids: &[u64]) -> Result<Vec<JsonCurseForgeMod>, Error> {
+        let body = JsonCurseForgeModsRequest {
+            mod_ids: mod_ids.to_vec(),
+        };
+        self.post("v1/mods", &body).await
+    }
+}
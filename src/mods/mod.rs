@@ -0,0 +1,183 @@
+pub mod curseforge;
+pub mod modrinth;
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    helpers,
+    instances::{Instance, LoaderKind},
+    types::Error,
+    utils,
+};
+
+const MODS_DIR: &str = "mods";
+
+/// Mod installed into the instance `mods/` directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledMod {
+    pub project_id: String,
+    pub version_id: String,
+    pub name: String,
+    pub version_number: String,
+    /// File name inside `mods/`
+    pub file_name: String,
+    pub sha1: String,
+    /// Installed as a dependency of another mod
+    #[serde(default)]
+    pub dependency: bool,
+}
+
+pub fn get_mods_dir(instance: &Instance) -> PathBuf {
+    instance.get_game_dir().join(MODS_DIR)
+}
+
+/// Loader names used by mod platforms, Quilt also loads Fabric mods
+pub fn get_loader_names(kind: LoaderKind) -> &'static [&'static str] {
+    match kind {
+        LoaderKind::Fabric => &["fabric"],
+        LoaderKind::Quilt => &["quilt", "fabric"],
+        LoaderKind::Forge => &["forge"],
+        LoaderKind::NeoForge => &["neoforge"],
+    }
+}
+
+/// Known hashes of the downloaded file
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileHashes<'a> {
+    pub sha1: Option<&'a str>,
+    pub sha512: Option<&'a str>,
+    /// Used by CurseForge when sha1 is missing
+    pub md5: Option<&'a str>,
+}
+
+impl FileHashes<'_> {
+    /// Check `data` against the known hashes, at least one hash is required
+    fn verify(&self, data: &[u8]) -> bool {
+        let check = |hash: Option<&str>, get_hash: fn(&[u8]) -> String| {
+            hash.is_none_or(|i| get_hash(data).eq_ignore_ascii_case(i))
+        };
+        (self.sha1.is_some() || self.sha512.is_some() || self.md5.is_some())
+            && check(self.sha1, utils::get_sha1)
+            && check(self.sha512, utils::get_sha512)
+            && check(self.md5, utils::get_md5)
+    }
+}
+
+/// Download file to `path` and verify its hashes, the file is written atomically
+///
+/// Existing file with the same hashes is kept
+pub async fn download_verified(
+    url: &str,
+    path: &Path,
+    hashes: &FileHashes<'_>,
+    client: &reqwest::Client,
+) -> Result<(), Error> {
+    if let Ok(data) = tokio::fs::read(path).await
+        && hashes.verify(&data)
+    {
+        return Ok(());
+    }
+
+    let data = helpers::http::get(url, Some(client)).await?;
+    if !hashes.verify(&data) {
+        return Err(Error::Mods(format!("Hash mismatch of {url}")));
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    tokio::fs::create_dir_all(dir).await?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_path = dir.join(format!("{file_name}.tmp"));
+    tokio::fs::write(&tmp_path, &data).await?;
+    tokio::fs::rename(tmp_path, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::{MockServer, TempLauncher};
+
+    fn sha1_only(sha1: &str) -> FileHashes<'_> {
+        FileHashes {
+            sha1: Some(sha1),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn download_is_rejected_on_hash_mismatch() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let data = b"mod jar".to_vec();
+        server.route("/a.jar", 200, data.clone());
+        let url = format!("{}/a.jar", server.url);
+        let path = temp.path().join("mods").join("a.jar");
+        let client = reqwest::Client::new();
+
+        let sha1 = utils::get_sha1(b"other jar");
+        let result = download_verified(&url, &path, &sha1_only(&sha1), &client).await;
+        assert!(matches!(result, Err(Error::Mods(_))));
+        assert!(!path.exists());
+
+        let sha512 = utils::get_sha512(&data);
+        let sha1 = utils::get_sha1(&data);
+        let result = download_verified(
+            &url,
+            &path,
+            &FileHashes {
+                sha1: Some(&sha1),
+                sha512: Some("0"),
+                md5: None,
+            },
+            &client,
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(!path.exists());
+
+        download_verified(
+            &url,
+            &path,
+            &FileHashes {
+                sha1: Some(&sha1),
+                sha512: Some(&sha512),
+                md5: None,
+            },
+            &client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn valid_existing_file_is_kept() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let path = temp.path().join("a.jar");
+        std::fs::write(&path, b"mod jar").unwrap();
+        let sha1 = utils::get_sha1(b"mod jar");
+
+        let url = format!("{}/a.jar", server.url);
+        download_verified(&url, &path, &sha1_only(&sha1), &reqwest::Client::new())
+            .await
+            .unwrap();
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn hashes_are_required() {
+        assert!(!FileHashes::default().verify(b"data"));
+        let sha1 = utils::get_sha1(b"data");
+        assert!(sha1_only(&sha1.to_uppercase()).verify(b"data"));
+        let md5 = utils::get_md5(b"data");
+        let hashes = FileHashes {
+            md5: Some(&md5),
+            ..Default::default()
+        };
+        assert!(hashes.verify(b"data"));
+        assert!(!hashes.verify(b"other"));
+    }
+}
@@ -0,0 +1,595 @@
+use std::collections::{HashMap, HashSet};
+
+use reqwest::Url;
+
+use super::{FileHashes, InstalledMod, download_verified, get_loader_names, get_mods_dir};
+use crate::{
+    helpers,
+    instances::{Instance, LoaderKind},
+    internal_types::shared_modrinth::{JsonModrinthSearch, JsonModrinthVersion},
+    types::Error,
+};
+
+const MODRINTH_API_URL: &str = "https://api.modrinth.com/v2";
+
+/// Modrinth api, `base_url` can point to a mirror or a mock server
+#[derive(Debug, Clone)]
+pub struct ModrinthApi {
+    pub base_url: String,
+}
+
+/// Search parameters, unset filters are not applied
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub query: String,
+    pub game_version: Option<String>,
+    pub loader: Option<LoaderKind>,
+    /// `mod`, `modpack`, `resourcepack`, `shader`...
+    pub project_type: Option<String>,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        SearchQuery {
+            query: String::new(),
+            game_version: None,
+            loader: None,
+            project_type: Some("mod".to_string()),
+            offset: 0,
+            limit: 20,
+        }
+    }
+}
+
+/// Mod versions chosen to be installed
+#[derive(Debug, Default)]
+struct Resolution {
+    versions: Vec<(JsonModrinthVersion, bool)>,
+    /// Project ids which must not be installed together with the resolved versions
+    incompatible: HashMap<String, String>,
+}
+
+/// Describe conflicts between the resolved versions and the installed mods
+///
+/// Incompatibilities are checked both ways: declared by the resolved versions
+/// and by `installed_versions` of the mods already in the instance
+fn find_conflicts(
+    resolution: &Resolution,
+    installed: &[InstalledMod],
+    installed_versions: &[JsonModrinthVersion],
+) -> Vec<String> {
+    let projects: Vec<&str> = resolution
+        .versions
+        .iter()
+        .map(|(i, _)| i.project_id.as_str())
+        .collect();
+    let mut conflicts: Vec<String> = installed
+        .iter()
+        .map(|i| i.project_id.as_str())
+        .chain(projects.iter().copied())
+        .filter_map(|project_id| {
+            resolution
+                .incompatible
+                .get(project_id)
+                .map(|by| format!("{project_id} is incompatible with {by}"))
+        })
+        .collect();
+
+    for version in installed_versions {
+        for dep in &version.dependencies {
+            if dep.dependency_type != "incompatible" {
+                continue;
+            }
+            if let Some(project_id) = &dep.project_id
+                && projects.contains(&project_id.as_str())
+            {
+                conflicts.push(format!(
+                    "{project_id} is incompatible with {}",
+                    version.name
+                ));
+            }
+        }
+    }
+    conflicts
+}
+
+impl Default for ModrinthApi {
+    fn default() -> Self {
+        ModrinthApi::new(MODRINTH_API_URL)
+    }
+}
+
+impl ModrinthApi {
+    pub fn new(base_url: &str) -> Self {
+        ModrinthApi {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn get_url(&self, path: &str, params: &[(&str, String)]) -> Result<Url, Error> {
+        Url::parse_with_params(&format!("{}/{path}", self.base_url), params)
+            .map_err(|err| Error::Mods(format!("Invalid Modrinth url: {err}")))
+    }
+
+    pub async fn search(&self, query: &SearchQuery) -> Result<JsonModrinthSearch, Error> {
+        // Фасеты: внешний массив — AND, внутренние — OR
+        let mut facets = Vec::new();
+        if let Some(project_type) = &query.project_type {
+            facets.push(vec![format!("project_type:{project_type}")]);
+        }
+        if let Some(game_version) = &query.game_version {
+            facets.push(vec![format!("versions:{game_version}")]);
+        }
+        if let Some(loader) = query.loader {
+            facets.push(
+                get_loader_names(loader)
+                    .iter()
+                    .map(|i| format!("categories:{i}"))
+                    .collect(),
+            );
+        }
+
+        let url = self.get_url(
+            "search",
+            &[
+                ("query", query.query.clone()),
+                ("facets", serde_json::to_string(&facets)?),
+                ("offset", query.offset.to_string()),
+                ("limit", query.limit.to_string()),
+            ],
+        )?;
+        let data = helpers::http::get(url.as_str(), None).await?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    pub async fn get_version(&self, id: &str) -> Result<JsonModrinthVersion, Error> {
+        let url = self.get_url(&format!("version/{id}"), &[])?;
+        let data = helpers::http::get(url.as_str(), None).await?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Get several versions at once, unknown ids are missing in the result
+    pub async fn get_versions_by_ids(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<JsonModrinthVersion>, Error> {
+        let url = self.get_url("versions", &[("ids", serde_json::to_string(ids)?)])?;
+        let data = helpers::http::get(url.as_str(), None).await?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Get versions of the project (id or slug) compatible with `game_version` and `loader`,
+    /// newest first
+    pub async fn get_versions(
+        &self,
+        project: &str,
+        game_version: &str,
+        loader: LoaderKind,
+    ) -> Result<Vec<JsonModrinthVersion>, Error> {
+        let loaders = get_loader_names(loader);
+        let url = self.get_url(
+            &format!("project/{project}/version"),
+            &[
+                ("game_versions", serde_json::to_string(&[game_version])?),
+                ("loaders", serde_json::to_string(loaders)?),
+            ],
+        )?;
+        let data = helpers::http::get(url.as_str(), None).await?;
+        let versions: Vec<JsonModrinthVersion> = serde_json::from_slice(&data)?;
+        // Фильтры повторяются на случай, если сервер их проигнорировал
+        Ok(versions
+            .into_iter()
+            .filter(|i| {
+                i.game_versions.iter().any(|v| v == game_version)
+                    && i.loaders.iter().any(|l| loaders.contains(&l.as_str()))
+            })
+            .collect())
+    }
+
+    /// Get the newest release of the project, or the newest version if there are no releases
+    pub async fn get_best_version(
+        &self,
+        project: &str,
+        game_version: &str,
+        loader: LoaderKind,
+    ) -> Result<JsonModrinthVersion, Error> {
+        let versions = self.get_versions(project, game_version, loader).await?;
+        let index = versions
+            .iter()
+            .position(|i| i.version_type == "release")
+            .unwrap_or(0);
+        versions.into_iter().nth(index).ok_or_else(|| {
+            Error::Mods(format!(
+                "No version of {project} for {game_version} {loader:?} found"
+            ))
+        })
+    }
+
+    /// Resolve `version` with its required dependencies recursively
+    ///
+    /// Dependencies already installed into the instance are not resolved again
+    async fn resolve(
+        &self,
+        instance: &Instance,
+        loader: LoaderKind,
+        version: JsonModrinthVersion,
+    ) -> Result<Resolution, Error> {
+        let mut resolution = Resolution::default();
+        let mut visited: HashSet<String> = HashSet::new();
+        let mut queue = vec![(version, false)];
+
+        while let Some((version, dependency)) = queue.pop() {
+            if !visited.insert(version.project_id.clone()) {
+                continue;
+            }
+            for dep in &version.dependencies {
+                match dep.dependency_type.as_str() {
+                    "required" => {}
+                    "incompatible" => {
+                        if let Some(project_id) = &dep.project_id {
+                            resolution
+                                .incompatible
+                                .insert(project_id.clone(), version.name.clone());
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                }
+
+                let is_installed = dep.project_id.as_ref().is_some_and(|project_id| {
+                    visited.contains(project_id)
+                        || instance.mods.iter().any(|i| &i.project_id == project_id)
+                });
+                if is_installed {
+                    continue;
+                }
+                let dep_version = match (&dep.version_id, &dep.project_id) {
+                    (Some(version_id), _) => self.get_version(version_id).await?,
+                    (None, Some(project_id)) => {
+                        self.get_best_version(project_id, &instance.version, loader)
+                            .await?
+                    }
+                    (None, None) => {
+                        log::warn!(
+                            "Dependency {:?} of {} is not hosted on Modrinth, install it manually",
+                            dep.file_name,
+                            version.name
+                        );
+                        continue;
+                    }
+                };
+                queue.push((dep_version, true));
+            }
+            resolution.versions.push((version, dependency));
+        }
+
+        Ok(resolution)
+    }
+
+    /// Install `project` (id or slug) into the instance `mods/` and record it in the manifest
+    ///
+    /// `version_id` selects the exact version, the newest compatible release is used otherwise.
+    /// Required dependencies are installed too, the installation is refused if any of the mods
+    /// is incompatible with another one. Returns the installed mods
+    pub async fn install_mod(
+        &self,
+        instance: &mut Instance,
+        project: &str,
+        version_id: Option<&str>,
+    ) -> Result<Vec<InstalledMod>, Error> {
+        let loader = instance
+            .loader
+            .as_ref()
+            .map(|i| i.kind)
+            .ok_or_else(|| Error::Mods(format!("Instance {} has no loader", instance.name)))?;
+        let version = match version_id {
+            Some(id) => self.get_version(id).await?,
+            None => {
+                self.get_best_version(project, &instance.version, loader)
+                    .await?
+            }
+        };
+
+        log::info!("Resolving dependencies of {}...", version.name);
+        let resolution = self.resolve(instance, loader, version).await?;
+        let projects: HashSet<&str> = resolution
+            .versions
+            .iter()
+            .map(|(i, _)| i.project_id.as_str())
+            .collect();
+        // Установленные моды тоже могут объявлять несовместимость с новыми
+        let installed_ids: Vec<String> = instance
+            .mods
+            .iter()
+            .filter(|i| !projects.contains(i.project_id.as_str()))
+            .map(|i| i.version_id.clone())
+            .collect();
+        let installed_versions = match installed_ids.is_empty() {
+            true => Vec::new(),
+            false => self.get_versions_by_ids(&installed_ids).await?,
+        };
+        let conflicts = find_conflicts(&resolution, &instance.mods, &installed_versions);
+        if !conflicts.is_empty() {
+            return Err(Error::Mods(conflicts.join(", ")));
+        }
+
+        let mods_dir = get_mods_dir(instance);
+        let client = reqwest::Client::builder().build()?;
+        let mut installed = Vec::new();
+        for (version, dependency) in resolution.versions {
+            let file = version
+                .get_primary_file()
+                .ok_or_else(|| Error::Mods(format!("Version {} has no files", version.name)))?;
+            if file.filename.contains(['/', '\\']) || file.filename.starts_with('.') {
+                return Err(Error::Mods(format!(
+                    "Invalid file name {:?}",
+                    file.filename
+                )));
+            }
+
+            log::info!("Installing mod {}...", file.filename);
+            let path = mods_dir.join(&file.filename);
+            download_verified(
+                &file.url,
+                &path,
+                &FileHashes {
+                    sha1: Some(&file.hashes.sha1),
+                    sha512: Some(&file.hashes.sha512),
+                    md5: None,
+                },
+                &client,
+            )
+            .await?;
+
+            // Старая версия того же проекта заменяется
+            if let Some(index) = instance
+                .mods
+                .iter()
+                .position(|i| i.project_id == version.project_id)
+            {
+                let old = instance.mods.remove(index);
+                if old.file_name != file.filename {
+                    let _ = tokio::fs::remove_file(mods_dir.join(&old.file_name)).await;
+                }
+            }
+            let installed_mod = InstalledMod {
+                project_id: version.project_id.clone(),
+                version_id: version.id.clone(),
+                name: version.name.clone(),
+                version_number: version.version_number.clone(),
+                file_name: file.filename.clone(),
+                sha1: file.hashes.sha1.clone(),
+                dependency,
+            };
+            instance.mods.push(installed_mod.clone());
+            // Манифест сохраняется после каждого мода, чтобы ошибка не оставила
+            // незаписанные файлы в `mods/`
+            instance.save()?;
+            installed.push(installed_mod);
+        }
+
+        log::info!("{} mods installed!", installed.len());
+        Ok(installed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{
+        helpers::testing::{MockServer, TempLauncher},
+        instances::{self, Loader},
+        utils,
+    };
+
+    fn version_json(project_id: &str, id: &str, dependencies: Value) -> Value {
+        json!({
+            "id": id,
+            "project_id": project_id,
+            "name": format!("{project_id} {id}"),
+            "version_number": "1.0.0",
+            "game_versions": ["1.21.8"],
+            "loaders": ["fabric"],
+            "version_type": "release",
+            "files": [],
+            "dependencies": dependencies,
+        })
+    }
+
+    fn create_instance(temp: &TempLauncher) -> Instance {
+        let mut instance = instances::create_instance(&temp.launcher, "test", "1.21.8").unwrap();
+        instance.loader = Some(Loader {
+            kind: LoaderKind::Fabric,
+            version: "0.16.14".to_string(),
+        });
+        instance
+    }
+
+    fn installed_mod(project_id: &str) -> InstalledMod {
+        InstalledMod {
+            project_id: project_id.to_string(),
+            version_id: format!("{project_id}1"),
+            name: project_id.to_string(),
+            version_number: "1.0.0".to_string(),
+            file_name: format!("{project_id}.jar"),
+            sha1: String::new(),
+            dependency: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn versions_are_filtered_by_game_version_and_loader() {
+        let server = MockServer::start().await;
+        let mut forge = version_json("A", "forge", json!([]));
+        forge["loaders"] = json!(["forge"]);
+        let mut old = version_json("A", "old", json!([]));
+        old["game_versions"] = json!(["1.20.1"]);
+        let mut beta = version_json("A", "beta", json!([]));
+        beta["version_type"] = json!("beta");
+        let release = version_json("A", "release", json!([]));
+        server.route_json("/project/A/version", json!([forge, old, beta, release]));
+        let api = ModrinthApi::new(&server.url);
+
+        let versions = api
+            .get_versions("A", "1.21.8", LoaderKind::Fabric)
+            .await
+            .unwrap();
+        let ids: Vec<&str> = versions.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["beta", "release"]);
+
+        // Quilt загружает и моды Fabric
+        let versions = api
+            .get_versions("A", "1.21.8", LoaderKind::Quilt)
+            .await
+            .unwrap();
+        assert_eq!(versions.len(), 2);
+
+        let best = api
+            .get_best_version("A", "1.21.8", LoaderKind::Fabric)
+            .await
+            .unwrap();
+        assert_eq!(best.id, "release");
+
+        let request = server.requests().pop().unwrap();
+        assert!(request.target.contains("game_versions="));
+        assert!(request.target.contains("loaders="));
+    }
+
+    #[tokio::test]
+    async fn dependencies_are_resolved_recursively() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let mut instance = create_instance(&temp);
+        instance.mods.push(installed_mod("E"));
+
+        let a = version_json(
+            "A",
+            "A1",
+            json!([
+                {"project_id": "B", "dependency_type": "required"},
+                {"project_id": "E", "dependency_type": "required"},
+                {"project_id": "F", "dependency_type": "optional"},
+            ]),
+        );
+        // B зависит обратно от A, цикл не должен зацикливать разрешение
+        let b = version_json(
+            "B",
+            "B1",
+            json!([
+                {"project_id": "A", "dependency_type": "required"},
+                {"version_id": "C1", "project_id": "C", "dependency_type": "required"},
+            ]),
+        );
+        let c = version_json(
+            "C",
+            "C1",
+            json!([{"project_id": "D", "dependency_type": "incompatible"}]),
+        );
+        server.route_json("/project/B/version", json!([b]));
+        server.route_json("/version/C1", c);
+        let api = ModrinthApi::new(&server.url);
+
+        let a: JsonModrinthVersion = serde_json::from_value(a).unwrap();
+        let resolution = api.resolve(&instance, LoaderKind::Fabric, a).await.unwrap();
+        let resolved: Vec<(&str, bool)> = resolution
+            .versions
+            .iter()
+            .map(|(i, dependency)| (i.id.as_str(), *dependency))
+            .collect();
+        assert_eq!(resolved, [("A1", false), ("B1", true), ("C1", true)]);
+        assert_eq!(
+            resolution.incompatible.get("D").map(String::as_str),
+            Some("C C1")
+        );
+        // Установленные и необязательные зависимости не запрашиваются
+        assert!(!server.was_requested("/project/E/version"));
+        assert!(!server.was_requested("/project/F/version"));
+        assert!(!server.was_requested("/project/A/version"));
+    }
+
+    #[test]
+    fn incompatibilities_are_reported_both_ways() {
+        let new: JsonModrinthVersion =
+            serde_json::from_value(version_json("A", "A1", json!([]))).unwrap();
+        let resolution = Resolution {
+            versions: vec![(new, false)],
+            incompatible: HashMap::from([("D".to_string(), "A A1".to_string())]),
+        };
+        let installed = [installed_mod("D"), installed_mod("G")];
+        let installed_versions: Vec<JsonModrinthVersion> = vec![
+            serde_json::from_value(version_json(
+                "G",
+                "G1",
+                json!([{"project_id": "A", "dependency_type": "incompatible"}]),
+            ))
+            .unwrap(),
+        ];
+
+        let conflicts = find_conflicts(&resolution, &installed, &installed_versions);
+        assert_eq!(
+            conflicts,
+            ["D is incompatible with A A1", "A is incompatible with G G1"]
+        );
+        assert!(find_conflicts(&resolution, &installed[1..], &[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn install_is_refused_on_conflict() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let mut instance = create_instance(&temp);
+        instance.mods.push(installed_mod("G"));
+
+        let mut a = version_json("A", "A1", json!([]));
+        a["files"] = json!([{
+            "hashes": {"sha1": "0", "sha512": "0"},
+            "url": format!("{}/a.jar", server.url),
+            "filename": "a.jar",
+            "primary": true,
+        }]);
+        let g = version_json(
+            "G",
+            "G1",
+            json!([{"project_id": "A", "dependency_type": "incompatible"}]),
+        );
+        server.route_json("/version/A1", a);
+        server.route_json("/versions", json!([g]));
+        let api = ModrinthApi::new(&server.url);
+
+        let result = api.install_mod(&mut instance, "A", Some("A1")).await;
+        assert!(matches!(result, Err(Error::Mods(msg)) if msg.contains("incompatible")));
+        assert!(!server.was_requested("/a.jar"));
+        assert_eq!(instance.mods.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn installed_mod_is_recorded_in_manifest() {
+        let server = MockServer::start().await;
+        let temp = TempLauncher::new();
+        let mut instance = create_instance(&temp);
+
+        let data = b"mod jar".to_vec();
+        let mut a = version_json("A", "A1", json!([]));
+        a["files"] = json!([{
+            "hashes": {"sha1": utils::get_sha1(&data), "sha512": utils::get_sha512(&data)},
+            "url": format!("{}/a.jar", server.url),
+            "filename": "a.jar",
+            "primary": true,
+        }]);
+        server.route_json("/project/A/version", json!([a]));
+        server.route("/a.jar", 200, data.clone());
+        let api = ModrinthApi::new(&server.url);
+
+        let installed = api.install_mod(&mut instance, "A", None).await.unwrap();
+        assert_eq!(installed.len(), 1);
+        let path = get_mods_dir(&instance).join("a.jar");
+        assert_eq!(std::fs::read(path).unwrap(), data);
+
+        let instance = instances::load_instance(&temp.launcher, "test").unwrap();
+        assert_eq!(instance.mods, installed);
+    }
+}
@@ -0,0 +1,514 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    process::{ExitStatus, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use futures::{Stream, StreamExt, future::BoxFuture};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::{Child, Command},
+    sync::{mpsc, watch},
+    task::JoinHandle,
+};
+
+use crate::{
+    game_log::{LogEvent, LogParser},
+    types::Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Line printed by the game process
+#[derive(Debug, Clone)]
+pub struct OutputLine {
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+type ExitResult = Option<Result<ExitStatus, std::io::ErrorKind>>;
+
+/// Hooks run after the exit, `None` once they were taken by the waiting task
+type ExitHooks = Arc<Mutex<Option<Vec<BoxFuture<'static, ()>>>>>;
+
+/// Number of the last output lines kept for crash reports
+const MAX_LAST_LINES: usize = 500;
+
+/// Number of lines queued for the taken output, newer lines are dropped for
+/// a slow reader, they are still kept in the last lines
+const OUTPUT_BUFFER: usize = 1000;
+/// Time of no output after which the held plain log event is returned
+const HELD_EVENT_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Time to wait for the remaining output after the process exit
+const OUTPUT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Output shared by the readers and the handle
+///
+/// Lines are queued only after the output is taken, so an unread output
+/// takes no more memory than the last lines
+#[derive(Default)]
+struct SharedOutput {
+    last_lines: VecDeque<OutputLine>,
+    sender: Option<mpsc::Sender<OutputLine>>,
+    /// The process closed its output, no more lines will be sent
+    closed: bool,
+}
+
+/// Handle of the running game process
+///
+/// The process is owned by a background task, so the handle can be
+/// polled for output, waited and killed without blocking the runtime
+pub struct GameProcess {
+    pid: u32,
+    started_at: SystemTime,
+    working_dir: PathBuf,
+    output: Arc<Mutex<SharedOutput>>,
+    output_taken: bool,
+    exit: watch::Receiver<ExitResult>,
+    kill: mpsc::Sender<()>,
+    /// Set when the process is stopped by [`GameProcess::kill`]
+    killed: Arc<AtomicBool>,
+    exit_hooks: ExitHooks,
+}
+
+fn read_lines(
+    reader: impl AsyncRead + Unpin + Send + 'static,
+    stream: OutputStream,
+    output: Arc<Mutex<SharedOutput>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    let line = OutputLine { stream, line };
+                    // Вывод нужно вычитывать, даже если его никто не читает,
+                    // иначе процесс заблокируется на переполненном pipe
+                    let Ok(mut output) = output.lock() else {
+                        continue;
+                    };
+                    if output.last_lines.len() == MAX_LAST_LINES {
+                        output.last_lines.pop_front();
+                    }
+                    output.last_lines.push_back(line.clone());
+                    if let Some(sender) = &output.sender {
+                        let _ = sender.try_send(line);
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    log::warn!("Failed to read game {stream:?}: {err}");
+                    break;
+                }
+            }
+        }
+    })
+}
+
+async fn wait_child(
+    mut child: Child,
+    mut kill: mpsc::Receiver<()>,
+    exit: watch::Sender<ExitResult>,
+    output: Arc<Mutex<SharedOutput>>,
+    readers: Vec<JoinHandle<()>>,
+    killed: Arc<AtomicBool>,
+    exit_hooks: ExitHooks,
+) {
+    let status = tokio::select! {
+        status = child.wait() => status,
+        Some(()) = kill.recv() => {
+            log::info!("Killing game process...");
+            killed.store(true, Ordering::SeqCst);
+            match child.kill().await {
+                Ok(()) => child.wait().await,
+                Err(err) => Err(err),
+            }
+        }
+    };
+
+    // Статус отправляется после вычитывания всего вывода, чтобы последние строки
+    // были доступны сразу после завершения. Дочерние процессы игры могут держать
+    // pipe открытым, поэтому ожидание ограничено
+    let _ = tokio::time::timeout(OUTPUT_TIMEOUT, futures::future::join_all(readers)).await;
+    if let Ok(mut output) = output.lock() {
+        // Поток вывода завершается вместе с отправителем
+        output.sender = None;
+        output.closed = true;
+    }
+
+    // Хуки выполняются до отправки статуса, чтобы ожидающий выход процесса
+    // не завершил runtime раньше них
+    let hooks = exit_hooks.lock().ok().and_then(|mut hooks| hooks.take());
+    for hook in hooks.unwrap_or_default() {
+        hook.await;
+    }
+
+    let _ = exit.send(Some(status.map_err(|err| err.kind())));
+}
+
+impl GameProcess {
+    /// Spawn `command` with piped stdout and stderr
+    pub fn spawn(mut command: Command) -> Result<Self, Error> {
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(false);
+
+        // Jvm пишет hs_err_pid*.log в рабочую директорию процесса
+        let working_dir = match command.as_std().get_current_dir() {
+            Some(dir) => std::path::absolute(dir)?,
+            None => std::env::current_dir()?,
+        };
+        let started_at = SystemTime::now();
+        let mut child = command.spawn()?;
+        let pid = child.id().unwrap_or_default();
+        log::info!("Game started with pid {pid}");
+
+        let output: Arc<Mutex<SharedOutput>> = Arc::default();
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(read_lines(stdout, OutputStream::Stdout, output.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(read_lines(stderr, OutputStream::Stderr, output.clone()));
+        }
+
+        let (exit_sender, exit) = watch::channel(None);
+        let (kill, kill_receiver) = mpsc::channel(1);
+        let killed = Arc::new(AtomicBool::new(false));
+        let exit_hooks: ExitHooks = Arc::new(Mutex::new(Some(Vec::new())));
+        tokio::spawn(wait_child(
+            child,
+            kill_receiver,
+            exit_sender,
+            output.clone(),
+            readers,
+            killed.clone(),
+            exit_hooks.clone(),
+        ));
+
+        Ok(GameProcess {
+            pid,
+            started_at,
+            working_dir,
+            output,
+            output_taken: false,
+            exit,
+            kill,
+            killed,
+            exit_hooks,
+        })
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn started_at(&self) -> SystemTime {
+        self.started_at
+    }
+
+    /// Working directory of the process
+    pub fn working_dir(&self) -> &Path {
+        &self.working_dir
+    }
+
+    /// Get up to `count` last lines printed by the process
+    pub fn last_lines(&self, count: usize) -> Vec<String> {
+        let Ok(output) = self.output.lock() else {
+            return Vec::new();
+        };
+        let skip = output.last_lines.len().saturating_sub(count);
+        output
+            .last_lines
+            .iter()
+            .skip(skip)
+            .map(|i| i.line.clone())
+            .collect()
+    }
+
+    /// Take stream of stdout and stderr lines
+    ///
+    /// The stream can be taken only once, it ends after the process closes its output.
+    /// It starts with the kept last lines, lines are dropped if the stream is not
+    /// read fast enough
+    pub fn take_output(&mut self) -> Option<impl Stream<Item = OutputLine> + use<>> {
+        if self.output_taken {
+            return None;
+        }
+        self.output_taken = true;
+
+        let (sender, mut receiver) = mpsc::channel(OUTPUT_BUFFER);
+        if let Ok(mut output) = self.output.lock() {
+            for line in &output.last_lines {
+                let _ = sender.try_send(line.clone());
+            }
+            if !output.closed {
+                output.sender = Some(sender);
+            }
+        }
+        Some(futures::stream::poll_fn(move |cx| receiver.poll_recv(cx)))
+    }
+
+    /// Take stream of parsed log events
+    ///
+    /// Shares the output with [`GameProcess::take_output`], so only one of them can be taken.
+    /// The last plain event is returned after no output comes for a short time
+    pub fn take_events(&mut self) -> Option<impl Stream<Item = LogEvent> + use<>> {
+        let output = self.take_output()?;
+        let state = (output, LogParser::new(), LogParser::new(), false);
+
+        let events = futures::stream::unfold(
+            state,
+            |(mut output, mut stdout, mut stderr, finished)| async move {
+                if finished {
+                    return None;
+                }
+                // Последнее plain событие ждет следующей строки, при простое вывода оно отдается сразу
+                let line = if stdout.is_holding() || stderr.is_holding() {
+                    tokio::time::timeout(HELD_EVENT_TIMEOUT, output.next())
+                        .await
+                        .ok()
+                } else {
+                    Some(output.next().await)
+                };
+
+                let mut finished = false;
+                let events: Vec<LogEvent> = match line {
+                    Some(Some(OutputLine {
+                        stream: OutputStream::Stdout,
+                        line,
+                    })) => stdout.push(&line),
+                    Some(Some(OutputLine {
+                        stream: OutputStream::Stderr,
+                        line,
+                    })) => stderr.push(&line),
+                    // Вывод закончился, незавершенные события отдаются как есть
+                    Some(None) => {
+                        finished = true;
+                        stdout.finish().into_iter().chain(stderr.finish()).collect()
+                    }
+                    None => stdout
+                        .flush_held()
+                        .into_iter()
+                        .chain(stderr.flush_held())
+                        .collect(),
+                };
+                Some((
+                    futures::stream::iter(events),
+                    (output, stdout, stderr, finished),
+                ))
+            },
+        )
+        .flatten()
+        .boxed();
+        Some(events)
+    }
+
+    /// Wait for the process to exit
+    pub fn wait(&self) -> impl Future<Output = Result<ExitStatus, Error>> + use<> {
+        let mut exit = self.exit.clone();
+        async move {
+            let result = *exit
+                .wait_for(Option::is_some)
+                .await
+                .map_err(|_| std::io::Error::other("Game process task stopped"))?;
+            let status = result.expect("exit status is set");
+            Ok(status.map_err(std::io::Error::from)?)
+        }
+    }
+
+    /// Run `hook` after the process exits, [`GameProcess::wait`] returns after the hook
+    ///
+    /// The hook is run right away if the process has already exited
+    pub async fn on_exit(&self, hook: impl Future<Output = ()> + Send + 'static) {
+        let mut hook = Some(Box::pin(hook) as BoxFuture<'static, ()>);
+        if let Ok(mut hooks) = self.exit_hooks.lock()
+            && let Some(hooks) = hooks.as_mut()
+        {
+            hooks.extend(hook.take());
+        }
+        if let Some(hook) = hook {
+            hook.await;
+        }
+    }
+
+    /// Check if the process has exited
+    pub fn is_running(&self) -> bool {
+        self.exit.borrow().is_none()
+    }
+
+    /// Check if the process was stopped with [`GameProcess::kill`]
+    pub fn was_killed(&self) -> bool {
+        self.killed.load(Ordering::SeqCst)
+    }
+
+    /// Kill the process, does nothing if it has already exited
+    pub async fn kill(&self) -> Result<(), Error> {
+        if self.is_running() {
+            // Ошибка отправки означает, что процесс уже завершился
+            let _ = self.kill.send(()).await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{crash, helpers::testing::TempLauncher};
+
+    #[tokio::test]
+    async fn killed_process_is_not_a_crash() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo started; sleep 30"]);
+        let process = GameProcess::spawn(command).unwrap();
+        assert!(process.is_running());
+
+        process.kill().await.unwrap();
+        let status = process.wait().await.unwrap();
+        assert!(!status.success());
+        assert!(process.was_killed());
+        let dir = std::env::temp_dir();
+        assert!(
+            crash::wait_for_crash(&process, &dir)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_process_is_a_crash() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo \"Unrecognized VM option 'Foo'\" >&2; exit 1"]);
+        let process = GameProcess::spawn(command).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("crash-{}", process.pid()));
+        let crash = crash::wait_for_crash(&process, &dir)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!process.was_killed());
+        assert_eq!(crash.exit_code, Some(1));
+        assert_eq!(crash.kind, crash::CrashKind::InvalidJvmArguments);
+    }
+
+    #[tokio::test]
+    async fn jvm_crash_log_is_found_in_working_dir() {
+        let temp = TempLauncher::new();
+        let working_dir = temp.path().join("work");
+        let game_dir = temp.path().join("game");
+        std::fs::create_dir_all(&working_dir).unwrap();
+        std::fs::create_dir_all(&game_dir).unwrap();
+
+        let mut command = Command::new("sh");
+        command
+            .args([
+                "-c",
+                "echo 'java.lang.OutOfMemoryError' > hs_err_pid$$.log; exit 134",
+            ])
+            .current_dir(&working_dir);
+        let process = GameProcess::spawn(command).unwrap();
+        assert_eq!(
+            process.working_dir(),
+            std::path::absolute(&working_dir).unwrap()
+        );
+
+        let crash = crash::wait_for_crash(&process, &game_dir)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(crash.jvm_crash_logs.len(), 1);
+        assert!(crash.jvm_crash_logs[0].starts_with(process.working_dir()));
+        assert_eq!(crash.kind, crash::CrashKind::OutOfMemory);
+    }
+
+    #[tokio::test]
+    async fn output_is_streamed_after_take() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo first; sleep 0.2; echo second >&2"]);
+        let mut process = GameProcess::spawn(command).unwrap();
+        // Строки до получения потока берутся из последних строк
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let output: Vec<_> = process.take_output().unwrap().collect().await;
+        let lines: Vec<_> = output.iter().map(|i| (i.stream, i.line.as_str())).collect();
+        assert_eq!(
+            lines,
+            [
+                (OutputStream::Stdout, "first"),
+                (OutputStream::Stderr, "second")
+            ]
+        );
+        assert!(process.take_output().is_none());
+    }
+
+    #[tokio::test]
+    async fn held_event_is_returned_while_running() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo '[19:24:45] [main/INFO]: Done'; sleep 30"]);
+        let mut process = GameProcess::spawn(command).unwrap();
+        let mut events = process.take_events().unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.message, "Done");
+        process.kill().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unread_output_is_bounded() {
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "i=0; while [ $i -lt 3000 ]; do echo $i; i=$((i+1)); done",
+        ]);
+        let mut process = GameProcess::spawn(command).unwrap();
+        process.wait().await.unwrap();
+
+        let last_lines = process.last_lines(usize::MAX);
+        assert_eq!(last_lines.len(), MAX_LAST_LINES);
+        assert_eq!(last_lines.last().map(String::as_str), Some("2999"));
+        // После выхода поток содержит только последние строки и сразу завершается
+        let output: Vec<_> = process.take_output().unwrap().collect().await;
+        assert_eq!(output.len(), MAX_LAST_LINES);
+        assert_eq!(output[0].line, "2500");
+    }
+
+    #[tokio::test]
+    async fn exit_hooks_run_before_wait_returns() {
+        let process = GameProcess::spawn(Command::new("true")).unwrap();
+        let done = Arc::new(AtomicBool::new(false));
+        let hook_done = done.clone();
+        process
+            .on_exit(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                hook_done.store(true, Ordering::SeqCst);
+            })
+            .await;
+        process.wait().await.unwrap();
+        assert!(done.load(Ordering::SeqCst));
+
+        // Процесс уже завершился, хук выполняется сразу
+        let done = Arc::new(AtomicBool::new(false));
+        let hook_done = done.clone();
+        process
+            .on_exit(async move { hook_done.store(true, Ordering::SeqCst) })
+            .await;
+        assert!(done.load(Ordering::SeqCst));
+    }
+}
@@ -1,14 +1,21 @@
 use std::{
-    fs,
+    collections::HashMap,
     path::{self, Path, PathBuf},
     process::Command,
 };
 
 use crate::{
-    internal_types::shared::VersionJson,
-    types::{Error, Launcher},
+    auth::AuthInfo,
+    install,
+    internal_types::shared::{_JsonVersionArg, VersionJson},
+    jvm::discovery::JavaInstallation,
+    process::GameProcess,
+    types::{Error, LaunchOptions, Launcher, Platform},
+    utils,
 };
 
+const DEFAULT_MAX_MEMORY: u32 = 1024;
+
 fn get_join_char<'a>() -> &'a str {
     #[cfg(unix)]
     {
@@ -33,15 +40,21 @@ fn get_libs(info: &VersionJson, minecraft_dir: &Path) -> Result<String, Error> {
             continue;
         }
 
-        let path = &lib.downloads.artifact.path;
-        let path = path::absolute(minecraft_dir.join("libraries").join(path))?;
+        let Some(artifact) = lib.get_artifact() else {
+            continue;
+        };
+        let path = path::absolute(minecraft_dir.join("libraries").join(&artifact.path))?;
 
         libs.push(path);
     }
 
-    libs.push(PathBuf::from(
-        ".minecraft/versions/1.21.8/1.21.8.jar",
-    ));
+    let jar_id = info.get_jar_id();
+    libs.push(path::absolute(
+        minecraft_dir
+            .join("versions")
+            .join(jar_id)
+            .join(format!("{jar_id}.jar")),
+    )?);
 
     let join_char = get_join_char();
     let mut libstr = Vec::new();
@@ -53,48 +66,394 @@ fn get_libs(info: &VersionJson, minecraft_dir: &Path) -> Result<String, Error> {
     Ok(libstr.join(join_char))
 }
 
-pub fn get_command(launcher: &Launcher, info: &VersionJson) -> Result<(), Error> {
-    let libs = get_libs(info, &launcher.path);
-
-    let mut binding = Command::new(
-        ".minecraft/runtime/java-runtime-delta/windows-x64/java-runtime-delta/bin/java.exe",
-    );
-
-    // dbg!(&libs);
-
-    let command = binding
-        .arg("-Xmx1G")
-    		// .arg("-XstartOnFirstThread")
-        .arg("-Djava.library.path=.minecraft/versions/1.21.8/natives")
-        .arg("-cp")
-        .arg(libs?)
-        .arg("net.minecraft.client.main.Main")
-        .args([
-            "--username",
-            "sigma_svinka",
-            "--version",
-            "1.21.8",
-            "--gameDir",
-            ".minecraft",
-            "--assetsDir",
-            ".minecraft/assets",
-            "--assetIndex",
-            "26",
-            "--uuid",
-            "6a9b09bb-2299-44a3-884d-e6668c6c5031",
-            "--accessToken",
-            "123456789123456789123456789", 
-            "--userType",
-            "legacy",
-            "--versionType",
-            "release",
-        ]).envs(std::env::vars()).env(
-        "DYLD_LIBRARY_PATH",
-        ".minecraft/versions/1.21.8/natives",
-    );
-
-    // dbg!(&command);
-
-    command.status()?;
-    Ok(())
+/// Replace `${name}` placeholders of `arg` with `values`
+///
+/// Unknown placeholders are kept as is
+fn replace_placeholders(arg: &str, values: &HashMap<&str, String>) -> String {
+    let mut result = String::with_capacity(arg.len());
+    let mut rest = arg;
+
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + len];
+        result.push_str(&rest[..start]);
+        match values.get(name) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Get jvm and game arguments of the version for enabled `features`
+fn get_version_args(info: &VersionJson, features: &[&str]) -> (Vec<String>, Vec<String>) {
+    if let Some(arguments) = &info.arguments {
+        let collect = |args: &[_JsonVersionArg]| {
+            args.iter()
+                .flat_map(|arg| arg.get_values(features))
+                .map(String::from)
+                .collect()
+        };
+        return (collect(&arguments.jvm), collect(&arguments.game));
+    }
+
+    // До 1.13 аргументы jvm не указывались в манифесте
+    let jvm = [
+        "-Djava.library.path=${natives_directory}",
+        "-cp",
+        "${classpath}",
+    ]
+    .map(String::from)
+    .to_vec();
+
+    let mut game: Vec<String> = info
+        .minecraft_arguments
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    if features.contains(&"has_custom_resolution") {
+        game.extend(
+            [
+                "--width",
+                "${resolution_width}",
+                "--height",
+                "${resolution_height}",
+            ]
+            .map(String::from),
+        );
+    }
+    if features.contains(&"is_demo_user") {
+        game.push("--demo".to_string());
+    }
+
+    (jvm, game)
+}
+
+/// Build command of the game
+///
+/// Libraries, assets and natives are taken from the shared `launcher` directory,
+/// saves, mods and configs are stored in `game_dir`
+pub fn get_command(
+    launcher: &Launcher,
+    game_dir: &Path,
+    info: &VersionJson,
+    java: &JavaInstallation,
+    options: &LaunchOptions,
+    auth: &AuthInfo,
+) -> Result<Command, Error> {
+    let libs = get_libs(info, &launcher.path)?;
+    let launcher_dir = path::absolute(&launcher.path)?;
+    let game_dir = path::absolute(game_dir)?;
+    let natives_dir = launcher_dir.join("versions").join(&info.id).join("natives");
+    let assets_dir = launcher_dir.join("assets");
+
+    let uuid = auth.uuid.simple().to_string();
+    let values = HashMap::from([
+        ("auth_player_name", auth.name.clone()),
+        ("auth_uuid", uuid.clone()),
+        ("auth_access_token", auth.access_token.expose().to_string()),
+        (
+            "auth_session",
+            format!("token:{}:{uuid}", auth.access_token.expose()),
+        ),
+        ("user_type", auth.user_type.clone()),
+        ("user_properties", "{}".to_string()),
+        ("clientid", String::new()),
+        ("auth_xuid", auth.xuid.clone()),
+        ("version_name", info.id.clone()),
+        ("version_type", info.version_type.clone()),
+        ("game_directory", game_dir.to_string_lossy().to_string()),
+        ("assets_root", assets_dir.to_string_lossy().to_string()),
+        ("game_assets", assets_dir.to_string_lossy().to_string()),
+        ("assets_index_name", info.assets.clone()),
+        (
+            "natives_directory",
+            natives_dir.to_string_lossy().to_string(),
+        ),
+        (
+            "library_directory",
+            launcher_dir.join("libraries").to_string_lossy().to_string(),
+        ),
+        ("classpath", libs),
+        ("classpath_separator", get_join_char().to_string()),
+        ("launcher_name", env!("CARGO_PKG_NAME").to_string()),
+        ("launcher_version", env!("CARGO_PKG_VERSION").to_string()),
+        (
+            "resolution_width",
+            options.width.unwrap_or_default().to_string(),
+        ),
+        (
+            "resolution_height",
+            options.height.unwrap_or_default().to_string(),
+        ),
+    ]);
+
+    let (mut jvm_args, game_args) = get_version_args(info, &options.get_features());
+
+    // Конфиг логирования переключает вывод игры на xml события log4j
+    if let (Some(path), Some(config)) = (
+        install::get_logging_config_path(launcher, info),
+        info.logging.as_ref().and_then(|i| i.client.as_ref()),
+    ) && path.exists()
+    {
+        let path = path::absolute(path)?;
+        jvm_args.push(config.argument.replace("${path}", &path.to_string_lossy()));
+    }
+
+    let mut command = Command::new(&java.path);
+
+    if let Some(min_memory) = options.min_memory {
+        command.arg(format!("-Xms{min_memory}M"));
+    }
+    command.arg(format!(
+        "-Xmx{}M",
+        options.max_memory.unwrap_or(DEFAULT_MAX_MEMORY)
+    ));
+
+    command
+        .args(
+            jvm_args
+                .iter()
+                .map(|arg| replace_placeholders(arg, &values)),
+        )
+        .args(&auth.jvm_args)
+        .args(&options.jvm_args)
+        .arg(&info.main_class)
+        .args(
+            game_args
+                .iter()
+                .map(|arg| replace_placeholders(arg, &values)),
+        )
+        .args(&options.game_args);
+
+    if options.fullscreen {
+        command.arg("--fullscreen");
+    }
+
+    if matches!(utils::get_platform(), Platform::Darwin) {
+        command.env("DYLD_LIBRARY_PATH", &natives_dir);
+    }
+    for key in &options.env_remove {
+        command.env_remove(key);
+    }
+    command.envs(&options.env);
+
+    let working_dir: PathBuf = match &options.working_dir {
+        Some(dir) => path::absolute(dir)?,
+        None => game_dir,
+    };
+    command.current_dir(working_dir);
+
+    Ok(command)
+}
+
+/// Start the game without blocking and return handle of its process
+pub fn launch(
+    launcher: &Launcher,
+    game_dir: &Path,
+    info: &VersionJson,
+    java: &JavaInstallation,
+    options: &LaunchOptions,
+    auth: &AuthInfo,
+) -> Result<GameProcess, Error> {
+    let command = get_command(launcher, game_dir, info, java, options, auth)?;
+    GameProcess::spawn(command.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{auth::offline::OfflineAccount, helpers::testing::TempLauncher};
+
+    fn get_version(extra: Value) -> VersionJson {
+        let mut value = json!({
+            "id": "test",
+            "assets": "17",
+            "assetIndex": { "url": "https://example.com/17.json" },
+            "complianceLevel": 1,
+            "mainClass": "net.minecraft.client.main.Main",
+            "minimumLauncherVersion": 21,
+            "type": "release",
+            "downloads": { "client": { "url": "https://example.com/client.jar" } },
+            "javaVersion": { "component": "java-runtime-delta", "majorVersion": 21 },
+            "libraries": [{ "name": "com.example:lib:1.0", "url": "https://maven.example.com" }],
+        });
+        for (key, extra) in extra.as_object().unwrap() {
+            value[key] = extra.clone();
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn get_java() -> JavaInstallation {
+        JavaInstallation {
+            path: PathBuf::from("java"),
+            version: "21.0.1".to_string(),
+            major_version: 21,
+            vendor: "Test".to_string(),
+            arch: "x86_64".to_string(),
+        }
+    }
+
+    fn get_args(temp: &TempLauncher, info: &VersionJson, options: &LaunchOptions) -> Vec<String> {
+        let auth = OfflineAccount::new("Steve").unwrap().get_auth_info();
+        let game_dir = temp.path().join("game");
+        let command =
+            get_command(&temp.launcher, &game_dir, info, &get_java(), options, &auth).unwrap();
+        command
+            .get_args()
+            .map(|i| i.to_string_lossy().to_string())
+            .collect()
+    }
+
+    fn modern_version() -> VersionJson {
+        get_version(json!({
+            "arguments": {
+                "jvm": ["-Djava.library.path=${natives_directory}", "-cp", "${classpath}"],
+                "game": [
+                    "--username", "${auth_player_name}",
+                    "--gameDir", "${game_directory}",
+                    "--version", "${version_name}",
+                    "--extra", "${unknown_value}",
+                    {
+                        "rules": [{ "action": "allow", "features": { "has_custom_resolution": true } }],
+                        "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"],
+                    },
+                    {
+                        "rules": [{ "action": "allow", "features": { "is_demo_user": true } }],
+                        "value": "--demo",
+                    },
+                ],
+            },
+        }))
+    }
+
+    #[test]
+    fn placeholders_are_replaced() {
+        let temp = TempLauncher::new();
+        let info = modern_version();
+        let args = get_args(&temp, &info, &LaunchOptions::default());
+
+        let launcher_dir = path::absolute(temp.path()).unwrap();
+        let classpath = [
+            launcher_dir.join("libraries/com/example/lib/1.0/lib-1.0.jar"),
+            launcher_dir.join("versions/test/test.jar"),
+        ]
+        .map(|i| i.to_string_lossy().to_string())
+        .join(get_join_char());
+        let expected = [
+            format!("-Xmx{DEFAULT_MAX_MEMORY}M"),
+            format!(
+                "-Djava.library.path={}",
+                launcher_dir.join("versions/test/natives").to_string_lossy()
+            ),
+            "-cp".to_string(),
+            classpath,
+            "net.minecraft.client.main.Main".to_string(),
+            "--username".to_string(),
+            "Steve".to_string(),
+            "--gameDir".to_string(),
+            launcher_dir.join("game").to_string_lossy().to_string(),
+            "--version".to_string(),
+            "test".to_string(),
+            // Неизвестные подстановки остаются как есть
+            "--extra".to_string(),
+            "${unknown_value}".to_string(),
+        ];
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn legacy_arguments_are_used_before_1_13() {
+        let temp = TempLauncher::new();
+        let info = get_version(json!({
+            "minecraftArguments": "--username ${auth_player_name} --session ${auth_session} --assetIndex ${assets_index_name}",
+        }));
+        let options = LaunchOptions {
+            width: Some(854),
+            height: Some(480),
+            demo: true,
+            ..Default::default()
+        };
+        let args = get_args(&temp, &info, &options);
+
+        let main = args
+            .iter()
+            .position(|i| i == "net.minecraft.client.main.Main")
+            .unwrap();
+        assert!(
+            args[..main]
+                .iter()
+                .any(|i| i.starts_with("-Djava.library.path="))
+        );
+        assert_eq!(args[main - 2], "-cp");
+        let uuid = OfflineAccount::new("Steve")
+            .unwrap()
+            .get_auth_info()
+            .uuid
+            .simple()
+            .to_string();
+        assert_eq!(
+            args[main + 1..],
+            [
+                "--username".to_string(),
+                "Steve".to_string(),
+                "--session".to_string(),
+                format!("token:0:{uuid}"),
+                "--assetIndex".to_string(),
+                "17".to_string(),
+                "--width".to_string(),
+                "854".to_string(),
+                "--height".to_string(),
+                "480".to_string(),
+                "--demo".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn feature_rules_follow_options() {
+        let temp = TempLauncher::new();
+        let info = modern_version();
+
+        let args = get_args(&temp, &info, &LaunchOptions::default());
+        assert!(
+            !args
+                .iter()
+                .any(|i| i == "--width" || i == "--demo" || i == "--fullscreen")
+        );
+
+        // Разрешение включается только вместе с шириной и высотой
+        let options = LaunchOptions {
+            width: Some(1280),
+            ..Default::default()
+        };
+        assert!(!get_args(&temp, &info, &options).contains(&"--width".to_string()));
+
+        let options = LaunchOptions {
+            width: Some(1280),
+            height: Some(720),
+            fullscreen: true,
+            min_memory: Some(512),
+            max_memory: Some(4096),
+            ..Default::default()
+        };
+        let args = get_args(&temp, &info, &options);
+        let width = args.iter().position(|i| i == "--width").unwrap();
+        assert_eq!(
+            args[width..width + 4],
+            ["--width", "1280", "--height", "720"]
+        );
+        assert_eq!(args.last().unwrap(), "--fullscreen");
+        assert_eq!(args[..2], ["-Xms512M", "-Xmx4096M"]);
+        assert_eq!(args.iter().filter(|i| i.starts_with("-Xmx")).count(), 1);
+    }
 }
@@ -0,0 +1,301 @@
+use std::{
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    process::GameProcess,
+    types::{Error, Launcher},
+};
+
+const SESSIONS_FILE: &str = "sessions.json";
+const SESSIONS_LOCK_FILE: &str = "sessions.lock";
+const GAME_DIR_LOCK_FILE: &str = ".launcher.lock";
+
+/// Running game session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub pid: u32,
+    pub version: String,
+    pub instance: Option<String>,
+    pub game_dir: PathBuf,
+    /// Seconds since the unix epoch
+    pub started_at: u64,
+    pub account: Option<String>,
+}
+
+/// Exclusive lock of the game directory, released on drop
+#[derive(Debug)]
+pub struct GameDirLock {
+    _file: File,
+    pub game_dir: PathBuf,
+}
+
+/// Lock `game_dir`, so it can't be used by two games at once
+pub fn lock_game_dir(game_dir: &Path) -> Result<GameDirLock, Error> {
+    std::fs::create_dir_all(game_dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(game_dir.join(GAME_DIR_LOCK_FILE))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(GameDirLock {
+            _file: file,
+            game_dir: game_dir.to_path_buf(),
+        }),
+        Err(std::fs::TryLockError::WouldBlock) => Err(Error::Session(format!(
+            "Game directory {game_dir:?} is already in use"
+        ))),
+        Err(std::fs::TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+    let normalize = |path: &Path| {
+        std::fs::canonicalize(path)
+            .or_else(|_| std::path::absolute(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    normalize(a) == normalize(b)
+}
+
+/// Lock `game_dir` for a new launch
+///
+/// The lock is held by the launcher, not by the game, so it is released if the
+/// launcher exits first. Live sessions of the registry using `game_dir` are
+/// checked as well
+pub fn acquire_game_dir(launcher: &Launcher, game_dir: &Path) -> Result<GameDirLock, Error> {
+    let session = list_sessions(launcher)?
+        .into_iter()
+        .find(|session| is_same_dir(&session.game_dir, game_dir));
+    if let Some(session) = session {
+        return Err(Error::Session(format!(
+            "Game directory {game_dir:?} is used by the game with pid {}",
+            session.pid
+        )));
+    }
+    lock_game_dir(game_dir)
+}
+
+/// Run blocking file operation of the registry on the blocking thread pool
+pub async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> Result<T, Error> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// Check if process with `pid` is still alive
+pub fn is_process_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return false;
+        };
+        // Сигнал 0 только проверяет существование процесса
+        let result = unsafe { libc::kill(pid, 0) };
+        result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+    #[cfg(windows)]
+    {
+        std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {pid}"), "/NH", "/FO", "CSV"])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{pid}\"")))
+            .unwrap_or(false)
+    }
+}
+
+/// Run `f` on the sessions list while holding the registry lock
+///
+/// Sessions of dead processes are removed before `f` is called
+fn with_sessions<T>(
+    launcher: &Launcher,
+    f: impl FnOnce(&mut Vec<Session>) -> T,
+) -> Result<T, Error> {
+    std::fs::create_dir_all(&launcher.path)?;
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(launcher.path.join(SESSIONS_LOCK_FILE))?;
+    lock.lock()?;
+
+    let path = launcher.path.join(SESSIONS_FILE);
+    let mut sessions: Vec<Session> = match std::fs::read(&path) {
+        Ok(data) => serde_json::from_slice(&data)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+
+    sessions.retain(|session| {
+        let alive = is_process_alive(session.pid);
+        if !alive {
+            log::info!("Removing stale session with pid {}", session.pid);
+        }
+        alive
+    });
+
+    let result = f(&mut sessions);
+
+    let tmp_path = launcher.path.join(format!("{SESSIONS_FILE}.tmp"));
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(&sessions)?)?;
+    std::fs::rename(tmp_path, path)?;
+
+    Ok(result)
+}
+
+/// List running sessions
+pub fn list_sessions(launcher: &Launcher) -> Result<Vec<Session>, Error> {
+    with_sessions(launcher, |sessions| sessions.clone())
+}
+
+pub fn register_session(launcher: &Launcher, session: Session) -> Result<(), Error> {
+    log::info!("Registering session with pid {}", session.pid);
+    with_sessions(launcher, |sessions| {
+        sessions.retain(|i| i.pid != session.pid);
+        sessions.push(session);
+    })
+}
+
+pub fn unregister_session(launcher: &Launcher, pid: u32) -> Result<(), Error> {
+    with_sessions(launcher, |sessions| sessions.retain(|i| i.pid != pid))
+}
+
+/// Register the started game and unregister it once the process exits
+///
+/// `lock` is held until the process exits, the session is unregistered
+/// before [`GameProcess::wait`] returns
+pub async fn track_session(
+    launcher: &Launcher,
+    process: &GameProcess,
+    lock: GameDirLock,
+    version: &str,
+    instance: Option<&str>,
+    account: Option<&str>,
+) -> Result<Session, Error> {
+    let started_at = process
+        .started_at()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+
+    let session = Session {
+        pid: process.pid(),
+        version: version.to_string(),
+        instance: instance.map(String::from),
+        game_dir: lock.game_dir.clone(),
+        started_at,
+        account: account.map(String::from),
+    };
+    let launcher_path = launcher.path.clone();
+    let registered = session.clone();
+    run_blocking({
+        let path = launcher_path.clone();
+        move || register_session(&Launcher { path }, registered)
+    })
+    .await?;
+
+    let pid = session.pid;
+    process
+        .on_exit(async move {
+            let launcher = Launcher {
+                path: launcher_path,
+            };
+            if let Err(err) = run_blocking(move || unregister_session(&launcher, pid)).await {
+                log::warn!("Failed to unregister session with pid {pid}: {err:?}");
+            }
+            drop(lock);
+        })
+        .await;
+
+    Ok(session)
+}
+
+/// Stop running session with `pid`
+pub fn stop_session(launcher: &Launcher, pid: u32) -> Result<(), Error> {
+    let is_known = list_sessions(launcher)?.iter().any(|i| i.pid == pid);
+    if !is_known {
+        return Err(Error::Session(format!("Session with pid {pid} not found")));
+    }
+
+    log::info!("Stopping session with pid {pid}...");
+    #[cfg(unix)]
+    {
+        let pid =
+            libc::pid_t::try_from(pid).map_err(|_| Error::Session(format!("Invalid pid {pid}")))?;
+        if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    #[cfg(windows)]
+    {
+        std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .status()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::TempLauncher;
+
+    fn get_session(pid: u32, game_dir: &Path) -> Session {
+        Session {
+            pid,
+            version: "1.21.8".to_string(),
+            instance: None,
+            game_dir: game_dir.to_path_buf(),
+            started_at: 0,
+            account: None,
+        }
+    }
+
+    #[test]
+    fn live_session_keeps_game_dir_busy() {
+        let temp = TempLauncher::new();
+        let game_dir = temp.path().join("game");
+
+        // Лаунчер завершился: блокировка снята, но игра из реестра еще работает
+        drop(lock_game_dir(&game_dir).unwrap());
+        let session = get_session(std::process::id(), &game_dir.join("."));
+        register_session(&temp.launcher, session).unwrap();
+        let result = acquire_game_dir(&temp.launcher, &game_dir);
+        assert!(matches!(result, Err(Error::Session(_))));
+
+        unregister_session(&temp.launcher, std::process::id()).unwrap();
+        let lock = acquire_game_dir(&temp.launcher, &game_dir).unwrap();
+        assert!(matches!(
+            acquire_game_dir(&temp.launcher, &game_dir),
+            Err(Error::Session(_))
+        ));
+        drop(lock);
+    }
+
+    #[test]
+    fn dead_session_is_removed() {
+        let temp = TempLauncher::new();
+        let game_dir = temp.path().join("game");
+        // Завершившийся процесс: тестовый бинарник только выводит список тестов
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap())
+            .arg("--list")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        register_session(&temp.launcher, get_session(pid, &game_dir)).unwrap();
+        assert!(list_sessions(&temp.launcher).unwrap().is_empty());
+        acquire_game_dir(&temp.launcher, &game_dir).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use derive_more::From;
 use serde::{Deserialize, Serialize};
@@ -69,6 +69,47 @@ pub struct Version {
     pub url: String,
 }
 
+/// Per-instance launch settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchOptions {
+    /// Minimal heap size in megabytes, `-Xms`
+    pub min_memory: Option<u32>,
+    /// Maximal heap size in megabytes, `-Xmx`
+    pub max_memory: Option<u32>,
+    /// Window width, enables the `has_custom_resolution` feature together with `height`
+    pub width: Option<u32>,
+    /// Window height, enables the `has_custom_resolution` feature together with `width`
+    pub height: Option<u32>,
+    pub fullscreen: bool,
+    /// Enables the `is_demo_user` feature
+    pub demo: bool,
+    /// Arguments added after the version jvm arguments
+    pub jvm_args: Vec<String>,
+    /// Arguments added after the version game arguments
+    pub game_args: Vec<String>,
+    /// Environment variables added to the inherited environment
+    pub env: BTreeMap<String, String>,
+    /// Environment variables removed from the inherited environment
+    pub env_remove: Vec<String>,
+    /// Working directory of the game process, defaults to the game directory
+    pub working_dir: Option<PathBuf>,
+}
+
+impl LaunchOptions {
+    /// Get version json features enabled by the options
+    pub fn get_features(&self) -> Vec<&str> {
+        let mut features = Vec::new();
+        if self.width.is_some() && self.height.is_some() {
+            features.push("has_custom_resolution");
+        }
+        if self.demo {
+            features.push("is_demo_user");
+        }
+        features
+    }
+}
+
 #[derive(Debug, From)]
 pub enum Error {
     #[from]
@@ -85,4 +126,16 @@ pub enum Error {
 
     #[from]
     Infallible(std::convert::Infallible),
+
+    Jvm(String),
+
+    Session(String),
+
+    Auth(String),
+
+    Instance(String),
+
+    Loader(String),
+
+    Mods(String),
 }
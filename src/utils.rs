@@ -1,8 +1,12 @@
 use std::{
     env, fs, io,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+use md5::Md5;
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha512};
 use tokio::{fs::File, io::BufReader, process::Command};
 
 use crate::{
@@ -47,6 +51,32 @@ pub async fn make_executable(path: &Path) -> Result<(), Error> {
     Ok(())
 }
 
+pub async fn get_file_sha1(path: &Path) -> Result<String, Error> {
+    let data = tokio::fs::read(path).await?;
+    let hash = Sha1::digest(&data);
+    Ok(hash.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+pub fn get_sha1(data: &[u8]) -> String {
+    let hash = Sha1::digest(data);
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn get_md5(data: &[u8]) -> String {
+    let hash = Md5::digest(data);
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn get_sha256(data: &[u8]) -> String {
+    let hash = Sha256::digest(data);
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn get_sha512(data: &[u8]) -> String {
+    let hash = Sha512::digest(data);
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
 pub async fn unzip(path: &Path, dest: &Path) -> Result<(), Error> {
     let file = std::fs::File::open(path)?;
 
@@ -80,6 +110,36 @@ pub async fn unzip(path: &Path, dest: &Path) -> Result<(), Error> {
     Ok(())
 }
 
+/// Copy directory recursively, entries for which `filter` returns `false` are skipped
+pub fn copy_dir(src: &Path, dest: &Path, filter: &dyn Fn(&Path) -> bool) -> Result<(), Error> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        if !filter(&path) {
+            continue;
+        }
+
+        let dest = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &dest, filter)?;
+        } else {
+            fs::copy(&path, &dest)?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn untar_gz(path: &Path, dest: &Path) -> Result<(), Error> {
+    let file = std::fs::File::open(path)?;
+
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    archive.set_preserve_permissions(true);
+    archive.unpack(dest)?;
+
+    Ok(())
+}
+
 pub fn get_minecraft_dir() -> PathBuf {
     let platform = get_platform();
     match platform {
@@ -101,6 +161,14 @@ pub fn get_minecraft_dir() -> PathBuf {
     }
 }
 
+/// Get seconds since the unix epoch
+pub fn get_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
 pub async fn get_versions_list() -> Result<VersionsList, Error> {
     log::info!("Getting versions list...");
     let versions_raw = helpers::http::get(VERSION_MANIFEST_URL, None).await?;